@@ -1,13 +1,477 @@
+use crate::backup;
 use crate::error::AppError;
+use crate::vault::fingerprint;
+use crate::models::{AuthEntrySummary, CopilotToken, NormalizePreview, TargetDir, TargetOutcome, WriteStatus};
+use chrono::Utc;
 use dirs_next::{data_local_dir, home_dir};
-use serde_json::{json, Value};
-use std::fs;
-use std::path::PathBuf;
-use std::process::Command;
-
-fn update_json_file(
-    file_path: &PathBuf,
-    transform: impl FnOnce(&mut Value),
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+#[cfg(unix)]
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
+
+pub const COPILOT_PROVIDER_ID: &str = "github-copilot";
+// Files written by earlier versions of this app keep provider entries under this key
+const NESTED_AUTH_KEY: &str = "auth";
+// Fields owned by the typed entry variants; anything else is carried through untouched
+const KNOWN_ENTRY_FIELDS: [&str; 6] = ["type", "refresh", "access", "expires", "key", "token"];
+// Entry fields holding credentials, which never leave the Rust side in clear
+const SECRET_FIELDS: [&str; 4] = ["access", "refresh", "key", "token"];
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+const STALE_LOCK_AGE: Duration = Duration::from_secs(30);
+const MAX_WRITE_ATTEMPTS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthLayout {
+    /// Provider entries at the top level of auth.json.
+    Flat,
+    /// Provider entries inside a top-level `auth` object.
+    Nested,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthEntry {
+    OAuth {
+        refresh: String,
+        access: String,
+        expires: i64,
+        extra: Map<String, Value>,
+    },
+    Api {
+        key: String,
+        extra: Map<String, Value>,
+    },
+    WellKnown {
+        key: String,
+        token: String,
+        extra: Map<String, Value>,
+    },
+    /// Anything we do not recognise, kept verbatim.
+    Unknown(Value),
+}
+
+impl AuthEntry {
+    pub fn from_value(value: &Value) -> Self {
+        let obj = match value.as_object() {
+            Some(obj) => obj,
+            None => return AuthEntry::Unknown(value.clone()),
+        };
+        let str_field = |name: &str| obj.get(name).and_then(|v| v.as_str()).map(|s| s.to_string());
+        let extra: Map<String, Value> = obj
+            .iter()
+            .filter(|(k, _)| !KNOWN_ENTRY_FIELDS.contains(&k.as_str()))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
+        let parsed = match obj.get("type").and_then(|v| v.as_str()) {
+            Some("oauth") => (|| {
+                Some(AuthEntry::OAuth {
+                    refresh: str_field("refresh")?,
+                    access: str_field("access")?,
+                    expires: obj.get("expires")?.as_i64()?,
+                    extra,
+                })
+            })(),
+            Some("api") => str_field("key").map(|key| AuthEntry::Api { key, extra }),
+            Some("wellknown") => (|| {
+                Some(AuthEntry::WellKnown {
+                    key: str_field("key")?,
+                    token: str_field("token")?,
+                    extra,
+                })
+            })(),
+            _ => None,
+        };
+        parsed.unwrap_or_else(|| AuthEntry::Unknown(value.clone()))
+    }
+
+    pub fn to_value(&self) -> Value {
+        let (mut map, extra) = match self {
+            AuthEntry::OAuth { refresh, access, expires, extra } => {
+                let mut map = Map::new();
+                map.insert("type".to_string(), json!("oauth"));
+                map.insert("refresh".to_string(), json!(refresh));
+                map.insert("access".to_string(), json!(access));
+                map.insert("expires".to_string(), json!(expires));
+                (map, extra)
+            }
+            AuthEntry::Api { key, extra } => {
+                let mut map = Map::new();
+                map.insert("type".to_string(), json!("api"));
+                map.insert("key".to_string(), json!(key));
+                (map, extra)
+            }
+            AuthEntry::WellKnown { key, token, extra } => {
+                let mut map = Map::new();
+                map.insert("type".to_string(), json!("wellknown"));
+                map.insert("key".to_string(), json!(key));
+                map.insert("token".to_string(), json!(token));
+                (map, extra)
+            }
+            AuthEntry::Unknown(value) => return value.clone(),
+        };
+        for (k, v) in extra {
+            map.insert(k.clone(), v.clone());
+        }
+        Value::Object(map)
+    }
+
+    pub fn entry_type(&self) -> &str {
+        match self {
+            AuthEntry::OAuth { .. } => "oauth",
+            AuthEntry::Api { .. } => "api",
+            AuthEntry::WellKnown { .. } => "wellknown",
+            AuthEntry::Unknown(value) => value.get("type").and_then(|v| v.as_str()).unwrap_or("unknown"),
+        }
+    }
+}
+
+/// An OpenCode auth.json. Keys and their order are kept exactly as read, so only the
+/// entries that are changed show up in a diff.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuthFile {
+    root: Map<String, Value>,
+}
+
+impl AuthFile {
+    pub fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Object(root) => Some(AuthFile { root }),
+            _ => None,
+        }
+    }
+
+    pub fn to_string_pretty(&self) -> Result<String, AppError> {
+        Ok(serde_json::to_string_pretty(&self.root)?)
+    }
+
+    /// Like `to_string_pretty`, with every credential replaced by its fingerprint so the
+    /// result can be shown in the UI.
+    pub fn to_string_redacted(&self) -> Result<String, AppError> {
+        let mut root = Value::Object(self.root.clone());
+        redact_secrets(&mut root);
+        Ok(serde_json::to_string_pretty(&root)?)
+    }
+
+    fn is_nested_container(value: &Value) -> bool {
+        value.as_object().map(|o| !o.contains_key("type")).unwrap_or(false)
+    }
+
+    fn container(&self, layout: AuthLayout) -> Option<&Map<String, Value>> {
+        match layout {
+            AuthLayout::Flat => Some(&self.root),
+            AuthLayout::Nested => self
+                .root
+                .get(NESTED_AUTH_KEY)
+                .filter(|v| Self::is_nested_container(v))
+                .and_then(|v| v.as_object()),
+        }
+    }
+
+    fn container_mut(&mut self, layout: AuthLayout, create: bool) -> Option<&mut Map<String, Value>> {
+        match layout {
+            AuthLayout::Flat => Some(&mut self.root),
+            AuthLayout::Nested => {
+                if create && !self.root.contains_key(NESTED_AUTH_KEY) {
+                    self.root.insert(NESTED_AUTH_KEY.to_string(), json!({}));
+                }
+                self.root
+                    .get_mut(NESTED_AUTH_KEY)
+                    .filter(|v| Self::is_nested_container(v))
+                    .and_then(|v| v.as_object_mut())
+            }
+        }
+    }
+
+    pub fn has_layout(&self, layout: AuthLayout) -> bool {
+        self.container(layout).is_some()
+    }
+
+    /// Every provider entry in the file, flat entries first.
+    pub fn entries(&self) -> Vec<(AuthLayout, String, AuthEntry)> {
+        let mut entries = Vec::new();
+        for layout in [AuthLayout::Flat, AuthLayout::Nested] {
+            if let Some(container) = self.container(layout) {
+                for (provider, value) in container {
+                    if layout == AuthLayout::Flat && provider == NESTED_AUTH_KEY && Self::is_nested_container(value) {
+                        continue;
+                    }
+                    entries.push((layout, provider.clone(), AuthEntry::from_value(value)));
+                }
+            }
+        }
+        entries
+    }
+
+    pub fn entry(&self, layout: AuthLayout, provider: &str) -> Option<AuthEntry> {
+        self.container(layout)?.get(provider).map(AuthEntry::from_value)
+    }
+
+    /// Flat entry if present, otherwise the nested one.
+    pub fn find_entry(&self, provider: &str) -> Option<AuthEntry> {
+        self.entry(AuthLayout::Flat, provider)
+            .or_else(|| self.entry(AuthLayout::Nested, provider))
+    }
+
+    /// Updates the entry in place so existing and unknown fields keep their position.
+    pub fn set_entry(&mut self, layout: AuthLayout, provider: &str, entry: &AuthEntry) {
+        let container = match self.container_mut(layout, true) {
+            Some(c) => c,
+            None => return,
+        };
+        let new_value = entry.to_value();
+        match (container.get_mut(provider), new_value) {
+            (Some(Value::Object(existing)), Value::Object(new_fields)) => {
+                existing.retain(|k, _| !KNOWN_ENTRY_FIELDS.contains(&k.as_str()) || new_fields.contains_key(k));
+                for (k, v) in new_fields {
+                    existing.insert(k, v);
+                }
+            }
+            (_, new_value) => {
+                container.insert(provider.to_string(), new_value);
+            }
+        }
+    }
+
+    /// The layout the file is written in, judged by where the other providers live.
+    /// `None` when the file holds no entries at all.
+    pub fn detect_layout(&self) -> Option<AuthLayout> {
+        let entries = self.entries();
+        let others = |layout: AuthLayout| {
+            entries
+                .iter()
+                .filter(|(l, p, _)| *l == layout && p != COPILOT_PROVIDER_ID)
+                .count()
+        };
+        let any = |layout: AuthLayout| entries.iter().any(|(l, _, _)| *l == layout);
+
+        let (flat, nested) = (others(AuthLayout::Flat), others(AuthLayout::Nested));
+        if flat > nested || (flat == nested && any(AuthLayout::Flat)) {
+            Some(AuthLayout::Flat)
+        } else if nested > 0 || any(AuthLayout::Nested) {
+            Some(AuthLayout::Nested)
+        } else {
+            None
+        }
+    }
+
+    /// Removes entries in the other layout that duplicate one in `layout`, dropping the
+    /// nested `auth` object once it is empty. Returns whether anything changed.
+    pub fn normalize(&mut self, layout: AuthLayout) -> bool {
+        let other = match layout {
+            AuthLayout::Flat => AuthLayout::Nested,
+            AuthLayout::Nested => AuthLayout::Flat,
+        };
+        let duplicates: Vec<String> = self
+            .entries()
+            .into_iter()
+            .filter(|(l, p, _)| *l == other && self.entry(layout, p).is_some())
+            .map(|(_, p, _)| p)
+            .collect();
+
+        let mut changed = false;
+        for provider in duplicates {
+            changed |= self.remove_entry(other, &provider);
+        }
+        if self.container(AuthLayout::Nested).map(|c| c.is_empty()).unwrap_or(false) {
+            self.root.shift_remove(NESTED_AUTH_KEY);
+            changed = true;
+        }
+        changed
+    }
+
+    /// Removes the entry, dropping the nested container too if it ends up empty.
+    pub fn remove_entry(&mut self, layout: AuthLayout, provider: &str) -> bool {
+        let removed = match self.container_mut(layout, false) {
+            Some(container) => container.shift_remove(provider).is_some(),
+            None => false,
+        };
+        if removed && layout == AuthLayout::Nested && self.container(layout).map(|c| c.is_empty()).unwrap_or(false) {
+            self.root.shift_remove(NESTED_AUTH_KEY);
+        }
+        removed
+    }
+}
+
+fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map.iter_mut() {
+                match v {
+                    Value::String(secret) if SECRET_FIELDS.contains(&k.as_str()) => {
+                        let (last4, hash) = fingerprint(secret);
+                        *secret = format!("…{} (sha256 {})", last4, hash);
+                    }
+                    _ => redact_secrets(v),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+/// Raw auth.json contents with the credentials fingerprinted, for diffs shown in the UI.
+/// Text that is not JSON cannot be redacted reliably and is withheld entirely.
+pub fn redact_auth_text(content: &str) -> String {
+    if content.trim().is_empty() {
+        return String::new();
+    }
+    match serde_json::from_str::<Value>(content) {
+        Ok(mut value) => {
+            redact_secrets(&mut value);
+            serde_json::to_string_pretty(&value).unwrap_or_default()
+        }
+        Err(_) => "<not valid JSON; contents hidden>".to_string(),
+    }
+}
+
+fn corrupt(file_path: &Path, reason: String) -> AppError {
+    AppError::CorruptAuthFile {
+        path: file_path.to_string_lossy().into_owned(),
+        backup: None,
+        reason,
+    }
+}
+
+// Keep an untouched copy of a file we refused to write next to it so the user can repair it
+// by hand. Only the write path does this; reads report the error and leave no trace.
+fn quarantine_corrupt_file(file_path: &Path, error: AppError) -> AppError {
+    let reason = match error {
+        AppError::CorruptAuthFile { reason, .. } => reason,
+        other => return other,
+    };
+    let file_name = file_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "auth.json".to_string());
+    // Every refused write lands here; the copies hold credentials, so do not pile them up
+    let backup = match existing_corrupt_copy(file_path, &file_name) {
+        Some(existing) => existing.to_string_lossy().into_owned(),
+        None => {
+            let backup = file_path.with_file_name(format!(
+                "{}.{}.corrupt",
+                file_name,
+                Utc::now().format("%Y%m%d-%H%M%S")
+            ));
+            match fs::copy(file_path, &backup) {
+                Ok(_) => backup.to_string_lossy().into_owned(),
+                Err(e) => format!("<copy failed: {}>", e),
+            }
+        }
+    };
+
+    AppError::CorruptAuthFile {
+        path: file_path.to_string_lossy().into_owned(),
+        backup: Some(backup),
+        reason,
+    }
+}
+
+/// A `.corrupt` copy next to the file with exactly its current contents, if one was made before.
+fn existing_corrupt_copy(file_path: &Path, file_name: &str) -> Option<PathBuf> {
+    let hash = Sha256::digest(fs::read(file_path).ok()?);
+    let prefix = format!("{}.", file_name);
+    fs::read_dir(file_path.parent()?)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .map(|n| n.to_string_lossy())
+                .is_some_and(|n| n.starts_with(&prefix) && n.ends_with(".corrupt"))
+        })
+        .find(|path| fs::read(path).is_ok_and(|bytes| Sha256::digest(bytes) == hash))
+}
+
+fn read_raw(file_path: &Path) -> Result<Option<Vec<u8>>, AppError> {
+    match fs::read(file_path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn parse_auth_bytes(file_path: &Path, bytes: Option<Vec<u8>>) -> Result<AuthFile, AppError> {
+    let bytes = match bytes {
+        Some(bytes) => bytes,
+        None => return Ok(AuthFile::default()),
+    };
+
+    let content = match String::from_utf8(bytes) {
+        Ok(content) => content,
+        Err(e) => return Err(corrupt(file_path, e.to_string())),
+    };
+    // An empty file holds no credentials worth protecting
+    if content.trim().is_empty() {
+        return Ok(AuthFile::default());
+    }
+
+    match serde_json::from_str::<Value>(&content) {
+        Ok(data) => AuthFile::from_value(data)
+            .ok_or_else(|| corrupt(file_path, "top-level value is not an object".to_string())),
+        Err(e) => Err(corrupt(file_path, e.to_string())),
+    }
+}
+
+/// Parses the file without side effects; a missing or empty file reads as empty.
+pub fn read_auth_file(file_path: &Path) -> Result<AuthFile, AppError> {
+    parse_auth_bytes(file_path, read_raw(file_path)?)
+}
+
+/// Advisory lock held as `auth.json.lock` next to the file; removed when dropped.
+pub struct AuthFileLock {
+    path: PathBuf,
+}
+
+impl Drop for AuthFileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+pub fn lock_auth_file(file_path: &Path) -> Result<AuthFileLock, AppError> {
+    let lock_path = file_path.with_extension("json.lock");
+    let deadline = Instant::now() + LOCK_TIMEOUT;
+
+    loop {
+        match OpenOptions::new().write(true).create_new(true).open(&lock_path) {
+            Ok(mut file) => {
+                let _ = write!(file, "{}", std::process::id());
+                return Ok(AuthFileLock { path: lock_path });
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                // A lock left behind by a crashed writer must not block us forever
+                let stale = fs::metadata(&lock_path)
+                    .and_then(|m| m.modified())
+                    .map(|t| t.elapsed().map(|age| age > STALE_LOCK_AGE).unwrap_or(false))
+                    .unwrap_or(false);
+                if stale {
+                    let _ = fs::remove_file(&lock_path);
+                    continue;
+                }
+                if Instant::now() >= deadline {
+                    return Err(AppError::WriteConflict(file_path.to_string_lossy().into_owned()));
+                }
+                sleep(LOCK_RETRY_INTERVAL);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+fn update_auth_file(
+    file_path: &Path,
+    transform: impl Fn(&mut AuthFile),
 ) -> Result<(), AppError> {
     if let Some(parent) = file_path.parent() {
         if !parent.exists() {
@@ -15,77 +479,217 @@ fn update_json_file(
         }
     }
 
-    let mut data: Value = if file_path.exists() {
-        if let Ok(content) = fs::read_to_string(file_path) {
-            serde_json::from_str(&content).unwrap_or_else(|_| json!({}))
-        } else {
-            json!({})
+    let _lock = lock_auth_file(file_path)?;
+    remove_orphaned_temp_file(file_path);
+
+    // OpenCode does not know about our lock and rewrites the file when it refreshes tokens,
+    // so re-run the transform if the file changed between our read and the rename
+    let mut snapshotted = false;
+    for _ in 0..MAX_WRITE_ATTEMPTS {
+        let original = read_raw(file_path)?;
+        // Refuse to touch a file we cannot parse rather than dropping the other providers in it
+        let mut data = parse_auth_bytes(file_path, original.clone()).map_err(|e| quarantine_corrupt_file(file_path, e))?;
+
+        transform(&mut data);
+
+        let content = data.to_string_pretty()?;
+        // One snapshot per write, so retries do not push older ones out of the rolling set
+        if !snapshotted {
+            backup::snapshot(file_path)?;
+            snapshotted = true;
+        }
+        if replace_file(file_path, &content, Some(&original))? {
+            return Ok(());
         }
-    } else {
-        json!({})
+    }
+
+    Err(AppError::WriteConflict(file_path.to_string_lossy().into_owned()))
+}
+
+// Hash of what this process last wrote to each file, so the watcher can tell our own
+// writes apart from OpenCode's
+fn own_writes() -> &'static Mutex<HashMap<PathBuf, Vec<u8>>> {
+    static OWN_WRITES: OnceLock<Mutex<HashMap<PathBuf, Vec<u8>>>> = OnceLock::new();
+    OWN_WRITES.get_or_init(Default::default)
+}
+
+/// Whether the file still holds exactly what this process last wrote to it.
+pub fn is_own_write(file_path: &Path) -> bool {
+    let written = match own_writes().lock().ok().and_then(|w| w.get(file_path).cloned()) {
+        Some(hash) => hash,
+        None => return false,
     };
+    match fs::read(file_path) {
+        Ok(bytes) => Sha256::digest(bytes).to_vec() == written,
+        Err(_) => false,
+    }
+}
+
+fn temp_path_for(file_path: &Path) -> PathBuf {
+    file_path.with_extension("json.tmp")
+}
+
+/// Removes a temp file left behind when a previous write crashed before its rename.
+/// Callers must hold the file's lock so an in-progress write is not mistaken for an orphan.
+pub fn remove_orphaned_temp_file(file_path: &Path) {
+    let temp_path = temp_path_for(file_path);
+    if temp_path.exists() {
+        let _ = fs::remove_file(temp_path);
+    }
+}
+
+/// Removes temp files left by crashed writes in every enabled target directory.
+pub fn cleanup_orphaned_temp_files(targets: &[TargetDir]) {
+    for target in targets.iter().filter(|t| t.enabled) {
+        let file_path = Path::new(&target.path).join("auth.json");
+        if !temp_path_for(&file_path).exists() {
+            continue;
+        }
+        if let Ok(_lock) = lock_auth_file(&file_path) {
+            remove_orphaned_temp_file(&file_path);
+        }
+    }
+}
+
+/// Replaces `file_path` with `content` without ever exposing a partial file: the temp file
+/// takes the original's mode and owner (0600 for new files) and is fsynced before the rename,
+/// and the directory is fsynced afterwards so the rename survives a power loss.
+pub fn write_file_atomic(file_path: &Path, content: &str) -> Result<(), AppError> {
+    replace_file(file_path, content, None).map(|_| ())
+}
+
+/// `write_file_atomic`, except that with `expected` the file is compared against it right
+/// before the rename, after the slow temp file write and fsync. Returns `false`, leaving the
+/// file alone, if someone else changed it in the meantime.
+fn replace_file(file_path: &Path, content: &str, expected: Option<&Option<Vec<u8>>>) -> Result<bool, AppError> {
+    let temp_path = temp_path_for(file_path);
+    let existing = fs::metadata(file_path).ok();
 
-    transform(&mut data);
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    let mode = existing.as_ref().map(|m| m.permissions().mode() & 0o7777).unwrap_or(0o600);
+    #[cfg(unix)]
+    options.mode(mode);
 
-    let temp_path = file_path.with_extension("json.tmp");
-    let content = serde_json::to_string_pretty(&data)?;
-    fs::write(&temp_path, content)?;
-    fs::rename(temp_path, file_path)?;
+    let mut file = options.open(&temp_path)?;
+    #[cfg(unix)]
+    {
+        // The mode passed to open is filtered through the umask, and an old temp file keeps its own
+        file.set_permissions(fs::Permissions::from_mode(mode))?;
+        if let Some(meta) = &existing {
+            // Only possible when running as root or changing to one of our own groups; best effort
+            let _ = std::os::unix::fs::fchown(&file, Some(meta.uid()), Some(meta.gid()));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = existing;
 
-    Ok(())
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+
+    if let Some(expected) = expected {
+        if read_raw(file_path)? != *expected {
+            let _ = fs::remove_file(&temp_path);
+            return Ok(false);
+        }
+    }
+    fs::rename(&temp_path, file_path)?;
+    if let Ok(mut writes) = own_writes().lock() {
+        writes.insert(file_path.to_path_buf(), Sha256::digest(content.as_bytes()).to_vec());
+    }
+
+    #[cfg(unix)]
+    if let Some(parent) = file_path.parent() {
+        fs::File::open(parent)?.sync_all()?;
+    }
+
+    Ok(true)
 }
 
-fn get_local_dir(app_name: &str) -> Option<PathBuf> {
-    data_local_dir().map(|mut p| {
-        p.push(app_name);
-        p
-    })
+/// Runs auth.json work on the blocking pool: waiting for a lock sleeps the thread, which
+/// must not happen on an async worker.
+pub async fn run_blocking<T, F>(work: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| AppError::System(e.to_string()))?
 }
 
-fn get_opencode_dirs() -> Vec<PathBuf> {
+fn env_dir(var: &str) -> Option<PathBuf> {
+    // The XDG spec says relative paths must be ignored
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+}
+
+/// Candidate OpenCode directories in priority order, each tagged with where it came from.
+/// The first entry is where a new auth.json is created when none exists yet.
+pub fn discover_opencode_dirs() -> Vec<(PathBuf, &'static str)> {
     let mut dirs = Vec::new();
-    if let Some(d) = get_local_dir("opencode") {
-        dirs.push(d); // %LOCALAPPDATA%\opencode on Windows
+    if let Some(d) = env_dir("OPENCODE_DATA_DIR") {
+        dirs.push((d, "opencode-env"));
+    }
+    if let Some(d) = env_dir("XDG_DATA_HOME") {
+        dirs.push((d.join("opencode"), "xdg-data"));
+    }
+    if let Some(d) = data_local_dir() {
+        dirs.push((d.join("opencode"), "local-data")); // %LOCALAPPDATA%\opencode on Windows
+    }
+    if let Some(d) = home_dir() {
+        dirs.push((d.join(".local").join("share").join("opencode"), "home-data")); // ~/.local/share/opencode
+    }
+    if let Some(d) = env_dir("OPENCODE_CONFIG_DIR") {
+        dirs.push((d, "opencode-env"));
+    }
+    if let Some(d) = env_dir("XDG_CONFIG_HOME") {
+        dirs.push((d.join("opencode"), "xdg-config"));
+    }
+    if let Some(d) = home_dir() {
+        dirs.push((d.join(".config").join("opencode"), "home-config")); // ~/.config/opencode
     }
-    if let Some(mut d) = home_dir() {
-        d.push(".local");
-        d.push("share");
-        d.push("opencode");
-        dirs.push(d); // ~/.local/share/opencode
+
+    let mut unique: Vec<(PathBuf, &'static str)> = Vec::new();
+    for (dir, source) in dirs {
+        if !unique.iter().any(|(d, _)| *d == dir) {
+            unique.push((dir, source));
+        }
     }
-    if let Some(mut d) = home_dir() {
-        d.push(".config");
-        d.push("opencode");
-        dirs.push(d); // ~/.config/opencode
+    unique
+}
+
+pub fn get_opencode_dirs() -> Vec<PathBuf> {
+    discover_opencode_dirs().into_iter().map(|(d, _)| d).collect()
+}
+
+/// The GitHub OAuth token behind the file's Copilot entry, if any.
+pub fn copilot_oauth_token(auth: &AuthFile) -> Option<String> {
+    // `refresh` holds the GitHub OAuth token; `access` may be a Copilot token
+    match auth.find_entry(COPILOT_PROVIDER_ID) {
+        Some(AuthEntry::OAuth { refresh, access, .. }) => Some(if refresh.is_empty() { access } else { refresh }),
+        _ => None,
     }
-    dirs
+    .filter(|t| !t.is_empty())
 }
 
-pub fn read_current_token() -> Option<String> {
+pub fn read_current_token(targets: &[TargetDir]) -> Option<String> {
     let mut latest_token = None;
     let mut latest_time = std::time::SystemTime::UNIX_EPOCH;
 
-    for mut dir in get_opencode_dirs() {
-        dir.push("auth.json");
+    for target in targets {
+        let dir = Path::new(&target.path).join("auth.json");
         if let Ok(metadata) = fs::metadata(&dir) {
             if let Ok(modified) = metadata.modified() {
                 if modified >= latest_time {
-                    if let Ok(content) = fs::read_to_string(&dir) {
-                        if let Ok(data) = serde_json::from_str::<Value>(&content) {
-                            let mut found_token = None;
-                            // Try direct format
-                            if let Some(token) = data.get("github-copilot").and_then(|v| v.get("access")).and_then(|v| v.as_str()) {
-                                found_token = Some(token.to_string());
-                            } 
-                            // Try nested format
-                            else if let Some(token) = data.get("auth").and_then(|v| v.get("github-copilot")).and_then(|v| v.get("access")).and_then(|v| v.as_str()) {
-                                found_token = Some(token.to_string());
-                            }
-                            
-                            if found_token.is_some() {
-                                latest_time = modified;
-                                latest_token = found_token;
-                            }
+                    if let Ok(auth) = read_auth_file(&dir) {
+                        let found_token = copilot_oauth_token(&auth);
+                        if found_token.is_some() {
+                            latest_time = modified;
+                            latest_token = found_token;
                         }
                     }
                 }
@@ -95,68 +699,292 @@ pub fn read_current_token() -> Option<String> {
     latest_token
 }
 
-pub fn update_auth_json(access_token: &str, _username: &str) -> Result<(), AppError> {
-    let mut wrote_any = false;
-    let dirs = get_opencode_dirs();
+/// The layout to write `auth` in. A file without entries gets the flat layout OpenCode
+/// itself writes; the layout is judged from the contents alone so a file is never
+/// rewritten into a shape the OpenCode reading it does not expect.
+fn file_layout(auth: &AuthFile) -> AuthLayout {
+    auth.detect_layout().unwrap_or(AuthLayout::Flat)
+}
 
-    let update_fn = |data: &mut Value| {
-        if !data.is_object() {
-            *data = json!({});
+/// Files whose duplicate Copilot entries would be removed by `normalize_auth_files`, with the
+/// change as a diff. A file that cannot be read is listed with its error instead.
+pub fn preview_normalize(targets: &[TargetDir]) -> Vec<NormalizePreview> {
+    let mut previews = Vec::new();
+    for target in targets.iter().filter(|t| t.enabled) {
+        let path = Path::new(&target.path).join("auth.json");
+        if !path.exists() {
+            continue;
         }
-        // Direct injection for newer opencode
-        data["github-copilot"] = json!({
-            "type": "oauth",
-            "refresh": access_token,
-            "access": access_token,
-            "expires": 0
-        });
-        // Nested inside auth object if it exists or create it
-        if let Some(auth_obj) = data.get_mut("auth") {
-            if auth_obj.is_object() {
-                auth_obj["github-copilot"] = json!({
-                    "type": "oauth",
-                    "refresh": access_token,
-                    "access": access_token,
-                    "expires": 0
+        let preview = |layout, diff, error| NormalizePreview {
+            path: path.to_string_lossy().into_owned(),
+            layout,
+            diff,
+            error,
+        };
+        let original = match read_auth_file(&path) {
+            Ok(original) => original,
+            Err(e) => {
+                previews.push(preview(None, String::new(), Some(e.to_string())));
+                continue;
+            }
+        };
+        let layout = file_layout(&original);
+        let mut normalized = original.clone();
+        if !normalized.normalize(layout) {
+            continue;
+        }
+        match (original.to_string_redacted(), normalized.to_string_redacted()) {
+            (Ok(before), Ok(after)) => previews.push(preview(
+                Some(layout),
+                backup::unified_diff(&before, &after, "current", "normalized"),
+                None,
+            )),
+            (Err(e), _) | (_, Err(e)) => previews.push(preview(None, String::new(), Some(e.to_string()))),
+        }
+    }
+    previews
+}
+
+/// Applies the normalization shown by `preview_normalize` to the given auth.json paths.
+pub fn normalize_auth_files(targets: &[TargetDir], paths: &[String]) -> Result<Vec<String>, AppError> {
+    let mut normalized = Vec::new();
+    for target in targets.iter().filter(|t| t.enabled) {
+        let path = Path::new(&target.path).join("auth.json");
+        let path_str = path.to_string_lossy().into_owned();
+        if !path.exists() || !paths.contains(&path_str) {
+            continue;
+        }
+        update_auth_file(&path, |data| {
+            let layout = file_layout(data);
+            data.normalize(layout);
+        })?;
+        normalized.push(path_str);
+    }
+    Ok(normalized)
+}
+
+/// Every provider entry in every readable auth.json, without the secrets.
+pub fn list_auth_entries(targets: &[TargetDir]) -> Vec<AuthEntrySummary> {
+    let mut summaries = Vec::new();
+    for target in targets {
+        let path = Path::new(&target.path).join("auth.json");
+        if !path.exists() {
+            continue;
+        }
+        if let Ok(auth) = read_auth_file(&path) {
+            for (layout, provider, entry) in auth.entries() {
+                summaries.push(AuthEntrySummary {
+                    path: path.to_string_lossy().into_owned(),
+                    layout,
+                    provider,
+                    entry_type: entry.entry_type().to_string(),
                 });
             }
-        } else {
-            data["auth"] = json!({
-                "github-copilot": {
-                    "type": "oauth",
-                    "refresh": access_token,
-                    "access": access_token,
-                    "expires": 0
-                }
-            });
         }
+    }
+    summaries
+}
+
+/// OpenCode keeps the GitHub OAuth token in `refresh` and the short-lived Copilot token in `access`,
+/// with `expires` in milliseconds. Without a Copilot token, `expires: 0` makes OpenCode fetch one itself.
+pub fn copilot_credential(oauth_token: &str, copilot_token: Option<&CopilotToken>) -> AuthEntry {
+    let (access, expires) = match copilot_token {
+        Some(copilot) => (copilot.token.clone(), copilot.expires_at * 1000),
+        None => (oauth_token.to_string(), 0),
     };
+    AuthEntry::OAuth {
+        refresh: oauth_token.to_string(),
+        access,
+        expires,
+        extra: Map::new(),
+    }
+}
 
-    // Only update existing auth.json files to avoid polluting unrelated directories
-    for mut dir in dirs.clone() {
-        dir.push("auth.json");
-        if dir.exists() {
-            let _ = update_json_file(&dir, update_fn);
-            wrote_any = true;
+fn write_outcome(path: &Path, result: Result<(), AppError>, first_error: &mut Option<AppError>) -> TargetOutcome {
+    let (status, error, backup) = match result {
+        Ok(()) => (WriteStatus::Written, None, None),
+        Err(e) => {
+            let message = e.to_string();
+            // Kept apart from other failures so the UI can ask what to do with the file
+            let (status, backup) = match &e {
+                AppError::CorruptAuthFile { backup, .. } => (WriteStatus::Corrupt, backup.clone()),
+                _ => (WriteStatus::Failed, None),
+            };
+            first_error.get_or_insert(e);
+            (status, Some(message), backup)
+        }
+    };
+    TargetOutcome {
+        path: path.to_string_lossy().into_owned(),
+        status,
+        error,
+        backup,
+    }
+}
+
+/// Writes the credential to every enabled target and reports what happened to each one.
+/// Fails only when nothing could be written, returning the first error encountered.
+pub fn update_auth_json(targets: &[TargetDir], credential: &AuthEntry, _username: &str) -> Result<Vec<TargetOutcome>, AppError> {
+    let update_fn = |data: &mut AuthFile| {
+        // Write only the layout this file already uses, so no stray duplicate is created
+        let layout = file_layout(data);
+        data.set_entry(layout, COPILOT_PROVIDER_ID, credential);
+    };
+
+    let mut outcomes = Vec::new();
+    let mut first_error = None;
+
+    // Only update existing auth.json files (or directories the user added explicitly)
+    // to avoid polluting unrelated directories
+    for target in targets {
+        let path = Path::new(&target.path).join("auth.json");
+        if target.enabled && (path.exists() || target.custom) {
+            outcomes.push(write_outcome(&path, update_auth_file(&path, update_fn), &mut first_error));
+        } else {
+            outcomes.push(TargetOutcome {
+                path: path.to_string_lossy().into_owned(),
+                status: WriteStatus::Skipped,
+                error: None,
+                backup: None,
+            });
         }
     }
 
     // If no auth.json exists anywhere, create one in the most likely default location (first in the list)
-    if !wrote_any {
-        if let Some(mut fallback_dir) = dirs.first().cloned() {
-            fallback_dir.push("auth.json");
-            let _ = update_json_file(&fallback_dir, update_fn);
+    if outcomes.iter().all(|o| o.status == WriteStatus::Skipped) {
+        if let Some(index) = targets.iter().position(|t| t.enabled) {
+            let fallback_path = Path::new(&targets[index].path).join("auth.json");
+            outcomes[index] = write_outcome(&fallback_path, update_auth_file(&fallback_path, update_fn), &mut first_error);
+        }
+    }
+
+    if outcomes.iter().any(|o| o.status == WriteStatus::Written) {
+        return Ok(outcomes);
+    }
+    Err(first_error.unwrap_or_else(|| AppError::System("No enabled OpenCode directory to write auth.json to".to_string())))
+}
+
+/// Strips the GitHub Copilot entry, in both layouts, from every enabled auth.json.
+/// Files without one are reported as skipped; fails only when every removal failed.
+pub fn remove_copilot_entries(targets: &[TargetDir]) -> Result<Vec<TargetOutcome>, AppError> {
+    let mut outcomes = Vec::new();
+    let mut first_error = None;
+
+    for target in targets {
+        let path = Path::new(&target.path).join("auth.json");
+        let has_entry = target.enabled
+            && path.exists()
+            && read_auth_file(&path)
+                .map(|auth| auth.find_entry(COPILOT_PROVIDER_ID).is_some())
+                .unwrap_or(true);
+        if !has_entry {
+            outcomes.push(TargetOutcome {
+                path: path.to_string_lossy().into_owned(),
+                status: WriteStatus::Skipped,
+                error: None,
+                backup: None,
+            });
+            continue;
         }
+        let result = update_auth_file(&path, |data| {
+            data.remove_entry(AuthLayout::Flat, COPILOT_PROVIDER_ID);
+            data.remove_entry(AuthLayout::Nested, COPILOT_PROVIDER_ID);
+        });
+        outcomes.push(write_outcome(&path, result, &mut first_error));
+    }
+
+    let failed = outcomes.iter().any(|o| matches!(o.status, WriteStatus::Failed | WriteStatus::Corrupt));
+    if failed && !outcomes.iter().any(|o| o.status == WriteStatus::Written) {
+        return Err(first_error.unwrap_or_else(|| AppError::System("Failed to sign out".to_string())));
+    }
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AUTH_JSON: &str = r#"{
+  "anthropic": {
+    "type": "api",
+    "key": "sk-ant",
+    "label": "work"
+  },
+  "x-custom": [
+    1,
+    2
+  ],
+  "github-copilot": {
+    "type": "oauth",
+    "refresh": "gho_old",
+    "access": "tid=old",
+    "expires": 0,
+    "enterpriseUrl": "ghe.example.com"
+  },
+  "openai": {
+    "type": "api",
+    "key": "sk-oa"
+  }
+}"#;
+
+    #[test]
+    fn set_entry_keeps_unknown_fields_and_key_order() {
+        let mut auth = AuthFile::from_value(serde_json::from_str(AUTH_JSON).unwrap()).unwrap();
+        let credential = AuthEntry::OAuth {
+            refresh: "gho_new".to_string(),
+            access: "tid=new".to_string(),
+            expires: 1700000000000,
+            extra: Map::new(),
+        };
+
+        auth.set_entry(AuthLayout::Flat, COPILOT_PROVIDER_ID, &credential);
+
+        let expected = AUTH_JSON
+            .replace("gho_old", "gho_new")
+            .replace("tid=old", "tid=new")
+            .replace(r#""expires": 0"#, r#""expires": 1700000000000"#);
+        assert_eq!(auth.to_string_pretty().unwrap(), expected);
+    }
+
+    #[test]
+    fn reading_a_corrupt_file_leaves_no_copy_behind() {
+        let dir = std::env::temp_dir().join(format!("auth-config-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("auth.json");
+        fs::write(&path, "{ \"anthropic\": ").unwrap();
+
+        let err = read_auth_file(&path).unwrap_err();
+
+        assert!(matches!(err, AppError::CorruptAuthFile { backup: None, .. }));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
     }
 
-    // Force kill OpenCode node processes to force reload
-    let _ = Command::new("powershell")
-        .args(&[
-            "-WindowStyle", "Hidden",
-            "-Command",
-            "Get-CimInstance Win32_Process | Where-Object { $_.Name -eq 'node.exe' -and $_.CommandLine -match 'opencode' } | Invoke-CimMethod -MethodName Terminate"
-        ])
-        .spawn();
+    #[test]
+    fn redacted_text_hides_credentials() {
+        let redacted = redact_auth_text(AUTH_JSON);
+
+        assert!(!redacted.contains("sk-ant"));
+        assert!(redacted.contains("\"label\": \"work\""));
+        assert_eq!(redact_auth_text("{ not json sk-ant"), "<not valid JSON; contents hidden>");
+    }
 
-    Ok(())
+    #[test]
+    fn refused_writes_keep_a_single_corrupt_copy() {
+        let dir = std::env::temp_dir().join(format!("auth-config-quarantine-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("auth.json");
+        fs::write(&path, "{ \"anthropic\": ").unwrap();
+
+        let first = update_auth_file(&path, |_| {}).unwrap_err();
+        let second = update_auth_file(&path, |_| {}).unwrap_err();
+
+        let copies = fs::read_dir(&dir)
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().ends_with(".corrupt"))
+            .count();
+        assert_eq!(copies, 1);
+        assert_eq!(first.to_string(), second.to_string());
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,165 @@
+use crate::auth_config::{lock_auth_file, redact_auth_text, write_file_atomic};
+use crate::database::app_data_dir;
+use crate::error::AppError;
+use crate::models::{AuthSnapshot, TargetDir};
+use chrono::{NaiveDateTime, Utc};
+use sha2::{Digest, Sha256};
+use similar::TextDiff;
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MAX_SNAPSHOTS_PER_TARGET: usize = 20;
+const SNAPSHOT_EXTENSION: &str = "json";
+// Records which file a snapshot directory belongs to
+const TARGET_MARKER: &str = "target";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+
+fn backups_dir() -> Result<PathBuf, AppError> {
+    Ok(app_data_dir()?.join("backups"))
+}
+
+fn canonical_target(target: &Path) -> PathBuf {
+    // auth.json itself may be missing, but its directory normally exists
+    match (target.parent().and_then(|dir| fs::canonicalize(dir).ok()), target.file_name()) {
+        (Some(dir), Some(name)) => dir.join(name),
+        _ => target.to_path_buf(),
+    }
+}
+
+fn target_key(target: &Path) -> String {
+    Sha256::digest(canonical_target(target).to_string_lossy().as_bytes())[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn target_dir(target: &Path) -> Result<PathBuf, AppError> {
+    Ok(backups_dir()?.join(target_key(target)))
+}
+
+/// Whether the snapshots in `dir` were taken of `target`, according to its marker.
+fn belongs_to(dir: &Path, target: &Path) -> bool {
+    match fs::read_to_string(dir.join(TARGET_MARKER)) {
+        Ok(marker) => canonical_target(Path::new(&marker)) == canonical_target(target),
+        Err(_) => false,
+    }
+}
+
+fn snapshot_path(target: &Path, id: &str) -> Result<PathBuf, AppError> {
+    // Ids are plain timestamps; anything else could escape the backups directory
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit() || c == '-') {
+        return Err(AppError::SnapshotNotFound(id.to_string()));
+    }
+    let dir = target_dir(target)?;
+    let path = dir.join(format!("{}.{}", id, SNAPSHOT_EXTENSION));
+    if !belongs_to(&dir, target) || !path.exists() {
+        return Err(AppError::SnapshotNotFound(id.to_string()));
+    }
+    Ok(path)
+}
+
+/// The auth.json of the configured target directory that `target_path` names. Snapshots
+/// are only ever read or restored for these files.
+pub fn resolve_target(targets: &[TargetDir], target_path: &str) -> Result<PathBuf, AppError> {
+    let wanted = canonical_target(Path::new(target_path));
+    targets
+        .iter()
+        .map(|t| Path::new(&t.path).join("auth.json"))
+        .find(|path| canonical_target(path) == wanted)
+        .ok_or_else(|| AppError::InvalidInput(format!("{} is not the auth.json of a target directory", target_path)))
+}
+
+fn snapshot_ids(dir: &Path) -> Result<Vec<String>, AppError> {
+    let mut ids: Vec<String> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some(SNAPSHOT_EXTENSION))
+        .filter_map(|path| path.file_stem().map(|s| s.to_string_lossy().into_owned()))
+        .collect();
+    // Timestamps sort lexicographically; newest first
+    ids.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(ids)
+}
+
+/// Copies the current contents of `target` into the rolling snapshot set, if it exists.
+pub fn snapshot(target: &Path) -> Result<Option<AuthSnapshot>, AppError> {
+    if !target.exists() {
+        return Ok(None);
+    }
+
+    let dir = target_dir(target)?;
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(TARGET_MARKER), canonical_target(target).to_string_lossy().as_bytes())?;
+
+    let now = Utc::now();
+    let id = now.format(TIMESTAMP_FORMAT).to_string();
+    let path = dir.join(format!("{}.{}", id, SNAPSHOT_EXTENSION));
+    let size = fs::copy(target, &path)?;
+
+    for old_id in snapshot_ids(&dir)?.into_iter().skip(MAX_SNAPSHOTS_PER_TARGET) {
+        let _ = fs::remove_file(dir.join(format!("{}.{}", old_id, SNAPSHOT_EXTENSION)));
+    }
+
+    Ok(Some(AuthSnapshot {
+        id,
+        target_path: target.to_string_lossy().into_owned(),
+        created_at: now.timestamp(),
+        size,
+    }))
+}
+
+pub fn list_snapshots() -> Result<Vec<AuthSnapshot>, AppError> {
+    let root = backups_dir()?;
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(root)?.filter_map(|e| e.ok()) {
+        let dir = entry.path();
+        let target_path = match fs::read_to_string(dir.join(TARGET_MARKER)) {
+            Ok(t) => t,
+            Err(_) => continue,
+        };
+        for id in snapshot_ids(&dir)? {
+            let path = dir.join(format!("{}.{}", id, SNAPSHOT_EXTENSION));
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            let created_at = NaiveDateTime::parse_from_str(&id, TIMESTAMP_FORMAT)
+                .map(|t| t.and_utc().timestamp())
+                .unwrap_or(0);
+            snapshots.push(AuthSnapshot {
+                id,
+                target_path: target_path.clone(),
+                created_at,
+                size,
+            });
+        }
+    }
+    snapshots.sort_by_key(|s| Reverse(s.created_at));
+    Ok(snapshots)
+}
+
+/// Unified diff from the snapshot to the current file contents, with credentials redacted.
+pub fn diff_snapshot(target: &Path, id: &str) -> Result<String, AppError> {
+    let old = redact_auth_text(&fs::read_to_string(snapshot_path(target, id)?)?);
+    let new = redact_auth_text(&fs::read_to_string(target).unwrap_or_default());
+
+    Ok(unified_diff(&old, &new, &format!("snapshot {}", id), &target.to_string_lossy()))
+}
+
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(old_label, new_label)
+        .to_string()
+}
+
+pub fn restore_snapshot(target: &Path, id: &str) -> Result<(), AppError> {
+    let content = fs::read_to_string(snapshot_path(target, id)?)?;
+    let _lock = lock_auth_file(target)?;
+    // Snapshot the file being replaced so the restore itself can be undone
+    snapshot(target)?;
+    write_file_atomic(target, &content)
+}
@@ -1,28 +1,48 @@
-use crate::database::init_db;
+use crate::auth_config;
+use crate::backup;
+use crate::database;
 use crate::error::AppError;
-use crate::models::{DeviceCodeResponse, GitHubProvider};
+use crate::health;
+use crate::history;
+use crate::migrations;
+use crate::models::{
+    AuthEntrySummary, AuthFileStatus, AuthSnapshot, DeviceCodeResponse, ImportReport, NormalizePreview, ProviderMetadata, ProviderSummary,
+    ReloadReport, SwitchEvent, SwitchOutcome, SwitchTrigger, TargetDir, TokenStatusChange, VaultStatus,
+};
 use crate::provider_service;
+use crate::reload::reload_opencode;
+use crate::settings;
 use crate::state::AppState;
+use crate::transfer;
+use crate::vault;
 use tauri::State;
 use tauri::AppHandle;
 use tauri_plugin_shell::ShellExt;
 use sqlx::SqlitePool;
+use std::path::PathBuf;
 
 async fn get_db(state: &State<'_, AppState>) -> Result<SqlitePool, AppError> {
-    let mut db_guard = state.db.lock().await;
-    if let Some(pool) = db_guard.as_ref() {
-        return Ok(pool.clone());
-    }
-    
-    let pool = init_db().await?;
-    *db_guard = Some(pool.clone());
-    Ok(pool)
+    state.pool().await
 }
 
 #[tauri::command]
-pub async fn list_providers(state: State<'_, AppState>) -> Result<Vec<GitHubProvider>, AppError> {
+pub async fn list_providers(state: State<'_, AppState>, tag: Option<String>) -> Result<Vec<ProviderSummary>, AppError> {
     let pool = get_db(&state).await?;
-    provider_service::list_providers(&pool).await
+    let key = state.vault_key().await;
+    let providers = provider_service::list_providers(&pool, tag.as_deref()).await?;
+    Ok(providers.iter().map(|p| provider_service::summarize(p, key.as_ref())).collect())
+}
+
+#[tauri::command]
+pub async fn update_provider_metadata(
+    state: State<'_, AppState>,
+    id: String,
+    metadata: ProviderMetadata,
+) -> Result<ProviderSummary, AppError> {
+    let pool = get_db(&state).await?;
+    let key = state.vault_key().await;
+    let provider = provider_service::update_provider_metadata(&pool, &id, metadata).await?;
+    Ok(provider_service::summarize(&provider, key.as_ref()))
 }
 
 #[tauri::command]
@@ -37,21 +57,49 @@ pub async fn complete_device_flow(
     device_code: String,
     interval: u64,
     expires_in: u64,
-) -> Result<GitHubProvider, AppError> {
+) -> Result<ProviderSummary, AppError> {
     let pool = get_db(&state).await?;
-    provider_service::complete_oauth_flow(&pool, &client_id, &device_code, interval, expires_in).await
+    let key = state.vault_key().await;
+    let provider =
+        provider_service::complete_oauth_flow(&pool, key.as_ref(), &client_id, &device_code, interval, expires_in).await?;
+    Ok(provider_service::summarize(&provider, key.as_ref()))
 }
 
+/// Finishes a device flow started with `start_device_flow` by replacing the token of an
+/// existing account instead of adding a new one.
 #[tauri::command]
-pub async fn delete_provider(state: State<'_, AppState>, id: String) -> Result<(), AppError> {
+pub async fn reauthorize_provider(
+    state: State<'_, AppState>,
+    id: String,
+    client_id: String,
+    device_code: String,
+    interval: u64,
+    expires_in: u64,
+) -> Result<ProviderSummary, AppError> {
+    let pool = get_db(&state).await?;
+    let key = state.vault_key().await;
+    let provider =
+        provider_service::reauthorize_provider(&pool, key.as_ref(), &id, &client_id, &device_code, interval, expires_in).await?;
+    Ok(provider_service::summarize(&provider, key.as_ref()))
+}
+
+#[tauri::command]
+pub async fn delete_provider(state: State<'_, AppState>, id: String) -> Result<bool, AppError> {
     let pool = get_db(&state).await?;
     provider_service::delete_provider(&pool, &id).await
 }
 
 #[tauri::command]
-pub async fn switch_provider(state: State<'_, AppState>, id: String) -> Result<(), AppError> {
+pub async fn sign_out(state: State<'_, AppState>) -> Result<SwitchOutcome, AppError> {
     let pool = get_db(&state).await?;
-    provider_service::switch_provider(&pool, &id).await
+    provider_service::sign_out(&pool).await
+}
+
+#[tauri::command]
+pub async fn switch_provider(state: State<'_, AppState>, id: String, force: Option<bool>) -> Result<SwitchOutcome, AppError> {
+    let pool = get_db(&state).await?;
+    let key = state.vault_key().await;
+    provider_service::switch_provider(&pool, key.as_ref(), &id, SwitchTrigger::Ui, force.unwrap_or(false)).await
 }
 
 #[tauri::command]
@@ -60,7 +108,192 @@ pub async fn open_url(app: AppHandle, url: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub async fn sync_active_account(state: State<'_, AppState>) -> Result<(), AppError> {
+pub async fn switch_back(state: State<'_, AppState>, force: Option<bool>) -> Result<SwitchOutcome, AppError> {
+    let pool = get_db(&state).await?;
+    let key = state.vault_key().await;
+    provider_service::switch_back(&pool, key.as_ref(), force.unwrap_or(false)).await
+}
+
+#[tauri::command]
+pub async fn get_active_provider_id(state: State<'_, AppState>) -> Result<Option<String>, AppError> {
+    let pool = get_db(&state).await?;
+    history::active_provider_id(&pool).await
+}
+
+#[tauri::command]
+pub async fn list_switch_history(state: State<'_, AppState>, limit: Option<i64>) -> Result<Vec<SwitchEvent>, AppError> {
+    let pool = get_db(&state).await?;
+    history::list_history(&pool, limit.unwrap_or(50)).await
+}
+
+/// Re-checks every token now instead of waiting for the background monitor.
+#[tauri::command]
+pub async fn check_token_health(state: State<'_, AppState>) -> Result<Vec<TokenStatusChange>, AppError> {
+    let pool = get_db(&state).await?;
+    let key = state.vault_key().await;
+    health::check_tokens(&pool, key.as_ref()).await
+}
+
+#[tauri::command]
+pub async fn sync_active_account(state: State<'_, AppState>) -> Result<Option<ProviderSummary>, AppError> {
+    let pool = get_db(&state).await?;
+    let key = state.vault_key().await;
+    let provider = provider_service::sync_active_account(&pool, key.as_ref()).await?;
+    Ok(provider.map(|p| provider_service::summarize(&p, key.as_ref())))
+}
+
+#[tauri::command]
+pub async fn list_auth_snapshots() -> Result<Vec<AuthSnapshot>, AppError> {
+    backup::list_snapshots()
+}
+
+#[tauri::command]
+pub async fn diff_auth_snapshot(state: State<'_, AppState>, target_path: String, id: String) -> Result<String, AppError> {
+    let pool = get_db(&state).await?;
+    let target = backup::resolve_target(&settings::list_target_dirs(&pool).await?, &target_path)?;
+    backup::diff_snapshot(&target, &id)
+}
+
+#[tauri::command]
+pub async fn restore_auth_snapshot(state: State<'_, AppState>, target_path: String, id: String) -> Result<ReloadReport, AppError> {
+    let pool = get_db(&state).await?;
+    let target = backup::resolve_target(&settings::list_target_dirs(&pool).await?, &target_path)?;
+    auth_config::run_blocking(move || backup::restore_snapshot(&target, &id)).await?;
+    tokio::task::spawn_blocking(reload_opencode)
+        .await
+        .map_err(|e| AppError::System(e.to_string()))?
+}
+
+#[tauri::command]
+pub async fn list_target_dirs(state: State<'_, AppState>) -> Result<Vec<TargetDir>, AppError> {
+    let pool = get_db(&state).await?;
+    settings::list_target_dirs(&pool).await
+}
+
+#[tauri::command]
+pub async fn add_target_dir(state: State<'_, AppState>, path: String) -> Result<Vec<TargetDir>, AppError> {
+    let pool = get_db(&state).await?;
+    settings::add_target_dir(&pool, &path).await?;
+    settings::list_target_dirs(&pool).await
+}
+
+#[tauri::command]
+pub async fn remove_target_dir(state: State<'_, AppState>, path: String) -> Result<Vec<TargetDir>, AppError> {
+    let pool = get_db(&state).await?;
+    settings::remove_target_dir(&pool, &path).await?;
+    settings::list_target_dirs(&pool).await
+}
+
+#[tauri::command]
+pub async fn set_target_dir_enabled(state: State<'_, AppState>, path: String, enabled: bool) -> Result<Vec<TargetDir>, AppError> {
+    let pool = get_db(&state).await?;
+    settings::set_target_dir_enabled(&pool, &path, enabled).await?;
+    settings::list_target_dirs(&pool).await
+}
+
+#[tauri::command]
+pub async fn list_auth_entries(state: State<'_, AppState>) -> Result<Vec<AuthEntrySummary>, AppError> {
+    let pool = get_db(&state).await?;
+    let targets = settings::list_target_dirs(&pool).await?;
+    Ok(auth_config::list_auth_entries(&targets))
+}
+
+#[tauri::command]
+pub async fn preview_normalize_auth(state: State<'_, AppState>) -> Result<Vec<NormalizePreview>, AppError> {
+    let pool = get_db(&state).await?;
+    let targets = settings::list_target_dirs(&pool).await?;
+    Ok(auth_config::preview_normalize(&targets))
+}
+
+#[tauri::command]
+pub async fn normalize_auth(state: State<'_, AppState>, paths: Vec<String>) -> Result<Vec<String>, AppError> {
+    let pool = get_db(&state).await?;
+    let targets = settings::list_target_dirs(&pool).await?;
+    auth_config::run_blocking(move || auth_config::normalize_auth_files(&targets, &paths)).await
+}
+
+#[tauri::command]
+pub async fn inspect_auth_files(state: State<'_, AppState>) -> Result<Vec<AuthFileStatus>, AppError> {
+    let pool = get_db(&state).await?;
+    provider_service::inspect_auth_files(&pool).await
+}
+
+#[tauri::command]
+pub async fn align_auth_files(state: State<'_, AppState>, id: String) -> Result<SwitchOutcome, AppError> {
+    let pool = get_db(&state).await?;
+    let key = state.vault_key().await;
+    provider_service::align_auth_files(&pool, key.as_ref(), &id).await
+}
+
+#[tauri::command]
+pub async fn vault_status(state: State<'_, AppState>) -> Result<VaultStatus, AppError> {
+    let pool = get_db(&state).await?;
+    Ok(VaultStatus {
+        initialized: vault::is_initialized(&pool).await?,
+        unlocked: state.vault_key().await.is_some(),
+    })
+}
+
+#[tauri::command]
+pub async fn setup_vault(state: State<'_, AppState>, passphrase: String) -> Result<(), AppError> {
+    let pool = get_db(&state).await?;
+    // Hold the key while tokens are re-encrypted so nothing is sealed with a stale one
+    let mut key_guard = state.vault_key.lock().await;
+    *key_guard = Some(vault::setup(&pool, &passphrase).await?);
+    // Copies taken before schema upgrades still hold every token in plaintext
+    migrations::remove_backups(&database::db_path()?)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unlock_vault(state: State<'_, AppState>, passphrase: String) -> Result<(), AppError> {
+    let pool = get_db(&state).await?;
+    let key = vault::unlock(&pool, &passphrase).await?;
+    *state.vault_key.lock().await = Some(key);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn lock_vault(state: State<'_, AppState>) -> Result<(), AppError> {
+    *state.vault_key.lock().await = None;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn change_vault_passphrase(
+    state: State<'_, AppState>,
+    current_passphrase: String,
+    new_passphrase: String,
+) -> Result<(), AppError> {
+    let pool = get_db(&state).await?;
+    // Hold the key while tokens are re-encrypted so nothing is sealed with a stale one
+    let mut key_guard = state.vault_key.lock().await;
+    *key_guard = Some(vault::change_passphrase(&pool, &current_passphrase, &new_passphrase).await?);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn export_accounts(state: State<'_, AppState>, path: String, passphrase: String) -> Result<usize, AppError> {
+    let pool = get_db(&state).await?;
+    let key = state.vault_key().await;
+    transfer::export_accounts(&pool, key.as_ref(), &PathBuf::from(path.trim()), &passphrase).await
+}
+
+#[tauri::command]
+pub async fn import_accounts(state: State<'_, AppState>, path: String, passphrase: String) -> Result<ImportReport, AppError> {
+    let pool = get_db(&state).await?;
+    let key = state.vault_key().await;
+    transfer::import_accounts(&pool, key.as_ref(), &PathBuf::from(path.trim()), &passphrase).await
+}
+
+#[tauri::command]
+pub async fn merge_database(
+    state: State<'_, AppState>,
+    path: String,
+    source_passphrase: Option<String>,
+) -> Result<ImportReport, AppError> {
     let pool = get_db(&state).await?;
-    provider_service::sync_active_account(&pool).await
+    let key = state.vault_key().await;
+    let source_passphrase = source_passphrase.filter(|p| !p.is_empty());
+    transfer::merge_database(&pool, key.as_ref(), &PathBuf::from(path.trim()), source_passphrase.as_deref()).await
 }
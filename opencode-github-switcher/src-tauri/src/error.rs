@@ -20,15 +20,106 @@ pub enum AppError {
     #[error("Provider not found: {0}")]
     ProviderNotFound(String),
 
+    #[error(
+        "Corrupt auth file {path} ({reason}); it was left untouched{}",
+        .backup.as_ref().map(|b| format!(" and a copy was saved to {}", b)).unwrap_or_default()
+    )]
+    CorruptAuthFile {
+        path: String,
+        /// Set once a write was refused and the file copied aside.
+        backup: Option<String>,
+        reason: String,
+    },
+
+    #[error("{0} kept changing while it was being written; please try again")]
+    WriteConflict(String),
+
+    #[error("Snapshot not found: {0}")]
+    SnapshotNotFound(String),
+
+    #[error("providers.db uses schema version {found}, but this version of the app only supports up to {supported}; please update the app")]
+    SchemaTooNew { found: i64, supported: i64 },
+
+    #[error("The token vault is locked; unlock it with your passphrase first")]
+    VaultLocked,
+
+    #[error("Incorrect passphrase")]
+    InvalidPassphrase,
+
+    #[error("Vault error: {0}")]
+    Vault(String),
+
+    #[error("GitHub rejected the token for {name} (HTTP {status}); re-authorize the account or force the switch")]
+    TokenRejected { name: String, status: u16 },
+
+    #[error("Could not verify the token for {name} with GitHub; force the switch to use it offline")]
+    TokenUnverified { name: String },
+
+    #[error("{name} is an account on {host}; only {supported} accounts can be re-authorized")]
+    UnsupportedHost { name: String, host: String, supported: String },
+
+    #[error("You signed in as {found}, but this account belongs to {expected}; sign in to GitHub as {expected} and try again")]
+    AccountMismatch { expected: String, found: String },
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
     #[error("System error: {0}")]
     System(String),
 }
 
+impl AppError {
+    /// Variant name the frontend can branch on without parsing the message.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::Database(_) => "Database",
+            AppError::Network(_) => "Network",
+            AppError::Io(_) => "Io",
+            AppError::Serialization(_) => "Serialization",
+            AppError::OAuth(_) => "OAuth",
+            AppError::ProviderNotFound(_) => "ProviderNotFound",
+            AppError::CorruptAuthFile { .. } => "CorruptAuthFile",
+            AppError::WriteConflict(_) => "WriteConflict",
+            AppError::SnapshotNotFound(_) => "SnapshotNotFound",
+            AppError::SchemaTooNew { .. } => "SchemaTooNew",
+            AppError::VaultLocked => "VaultLocked",
+            AppError::InvalidPassphrase => "InvalidPassphrase",
+            AppError::Vault(_) => "Vault",
+            AppError::TokenRejected { .. } => "TokenRejected",
+            AppError::TokenUnverified { .. } => "TokenUnverified",
+            AppError::UnsupportedHost { .. } => "UnsupportedHost",
+            AppError::AccountMismatch { .. } => "AccountMismatch",
+            AppError::InvalidInput(_) => "InvalidInput",
+            AppError::System(_) => "System",
+        }
+    }
+}
+
+// Commands reject with `{ kind, message }`
 impl serde::Serialize for AppError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        use serde::ser::SerializeStruct;
+        let mut error = serializer.serialize_struct("AppError", 2)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_kind_and_message() {
+        let error = AppError::TokenUnverified { name: "octocat".to_string() };
+
+        let value = serde_json::to_value(&error).unwrap();
+
+        assert_eq!(value["kind"], "TokenUnverified");
+        assert_eq!(value["message"], error.to_string());
     }
 }
@@ -1,10 +1,22 @@
 use crate::error::AppError;
-use crate::models::{AccessTokenResponse, DeviceCodeResponse, GitHubUser};
-use reqwest::Client;
+use crate::models::{AccessTokenResponse, CopilotToken, DeviceCodeResponse, GitHubUser, TokenStatus, TokenValidation};
+use reqwest::{Client, StatusCode};
 use std::time::Duration;
 use tokio::time::sleep;
 
+/// Host recorded for accounts; the device flow only talks to github.com for now.
+pub const GITHUB_HOST: &str = "github.com";
 const GITHUB_API_URL: &str = "https://api.github.com";
+// Lets a local mock stand in for the Copilot token endpoint
+const COPILOT_API_URL_ENV: &str = "OPENCODE_SWITCHER_COPILOT_API_URL";
+// Every switch waits for the exchange, so a stalled endpoint must not hold it up for long
+const COPILOT_TOKEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn copilot_api_url() -> String {
+    std::env::var(COPILOT_API_URL_ENV)
+        .map(|u| u.trim_end_matches('/').to_string())
+        .unwrap_or_else(|_| GITHUB_API_URL.to_string())
+}
 
 pub async fn get_device_code(client_id: &str) -> Result<DeviceCodeResponse, AppError> {
     let client = Client::new();
@@ -97,3 +109,103 @@ pub async fn get_user_info(access_token: &str) -> Result<GitHubUser, AppError> {
     let user: GitHubUser = res.json().await?;
     Ok(user)
 }
+
+/// Checks the token against `/user`, telling a revoked token apart from a failed request.
+pub async fn validate_token(access_token: &str, timeout: Duration) -> TokenValidation {
+    let network_error = |http_status: Option<u16>| TokenValidation {
+        status: TokenStatus::NetworkError,
+        http_status,
+        user: None,
+    };
+    let client = match Client::builder().timeout(timeout).build() {
+        Ok(client) => client,
+        Err(_) => return network_error(None),
+    };
+    let res = match client
+        .get(format!("{}/user", GITHUB_API_URL))
+        .header("Authorization", format!("Bearer {}", access_token))
+        .header("Accept", "application/vnd.github.v3+json")
+        .header("User-Agent", "OpenCode-GitHub-Switcher")
+        .send()
+        .await
+    {
+        Ok(res) => res,
+        Err(_) => return network_error(None),
+    };
+
+    let status = res.status();
+    if status.is_success() {
+        return match res.json::<GitHubUser>().await {
+            Ok(user) => TokenValidation {
+                status: TokenStatus::Valid,
+                http_status: Some(status.as_u16()),
+                user: Some(user),
+            },
+            Err(_) => network_error(Some(status.as_u16())),
+        };
+    }
+    // A 403 is also how GitHub reports an exhausted rate limit, which says nothing about the token
+    let rate_limited = res
+        .headers()
+        .get("x-ratelimit-remaining")
+        .map(|v| v == "0")
+        .unwrap_or(false);
+    let revoked = status == StatusCode::UNAUTHORIZED || (status == StatusCode::FORBIDDEN && !rate_limited);
+    TokenValidation {
+        status: if revoked { TokenStatus::Revoked } else { TokenStatus::NetworkError },
+        http_status: Some(status.as_u16()),
+        user: None,
+    }
+}
+
+pub async fn get_copilot_token(access_token: &str) -> Result<CopilotToken, AppError> {
+    fetch_copilot_token(&copilot_api_url(), access_token).await
+}
+
+async fn fetch_copilot_token(api_url: &str, access_token: &str) -> Result<CopilotToken, AppError> {
+    let client = Client::builder().timeout(COPILOT_TOKEN_TIMEOUT).build()?;
+    let res = client
+        .get(format!("{}/copilot_internal/v2/token", api_url))
+        .header("Authorization", format!("token {}", access_token))
+        .header("Accept", "application/json")
+        .header("User-Agent", "OpenCode-GitHub-Switcher")
+        .send()
+        .await?;
+
+    if !res.status().is_success() {
+        return Err(AppError::OAuth(format!("Failed to fetch Copilot token: {}", res.status())));
+    }
+
+    let token: CopilotToken = res.json().await?;
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_server;
+
+    #[tokio::test]
+    async fn exchanges_the_oauth_token_for_a_copilot_token() {
+        let (url, requests) = spawn_server(|_, path| match path {
+            "/copilot_internal/v2/token" => (200, r#"{"token":"tid=abc;exp=1700000000","expires_at":1700000000,"refresh_in":1500}"#.to_string()),
+            _ => (404, "{}".to_string()),
+        })
+        .await;
+
+        let token = fetch_copilot_token(&url, "gho_abc").await.unwrap();
+
+        assert_eq!(token.token, "tid=abc;exp=1700000000");
+        assert_eq!(token.expires_at, 1700000000);
+        assert_eq!(requests.lock().unwrap()[0].method, "GET");
+    }
+
+    #[tokio::test]
+    async fn reports_a_rejected_exchange() {
+        let (url, _) = spawn_server(|_, _| (401, r#"{"message":"Bad credentials"}"#.to_string())).await;
+
+        let err = fetch_copilot_token(&url, "gho_revoked").await.unwrap_err();
+
+        assert!(err.to_string().contains("401"), "{}", err);
+    }
+}
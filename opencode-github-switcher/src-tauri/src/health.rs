@@ -0,0 +1,84 @@
+use crate::error::AppError;
+use crate::github_oauth::{validate_token, GITHUB_HOST};
+use crate::models::{TokenStatus, TokenStatusChange, TokenValidation};
+use crate::provider_service::list_providers;
+use crate::state::AppState;
+use crate::vault::{open_token, VaultKey};
+use chrono::Utc;
+use sqlx::SqlitePool;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const TOKEN_STATUS_CHANGED_EVENT: &str = "token-status-changed";
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub async fn record_validation(pool: &SqlitePool, id: &str, result: &TokenValidation) -> Result<(), AppError> {
+    sqlx::query("UPDATE github_providers SET last_validated_at = ?, token_status = ?, last_http_status = ? WHERE id = ?")
+        .bind(Utc::now().timestamp())
+        .bind(result.status.as_str())
+        .bind(result.http_status.map(i64::from))
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Checks every stored token against GitHub and records the result. Returns the accounts
+/// whose status changed. Tokens that cannot be read while the vault is locked are skipped.
+pub async fn check_tokens(pool: &SqlitePool, key: Option<&VaultKey>) -> Result<Vec<TokenStatusChange>, AppError> {
+    let mut changes = Vec::new();
+
+    for provider in list_providers(pool, None).await? {
+        // Only github.com accounts can be checked against the API we talk to
+        if provider.host != GITHUB_HOST {
+            continue;
+        }
+        let token = match open_token(key, &provider.access_token) {
+            Ok(token) => token,
+            Err(_) => continue,
+        };
+
+        let result = validate_token(&token, REQUEST_TIMEOUT).await;
+        record_validation(pool, &provider.id, &result).await?;
+
+        // A first check that finds the token working is not news
+        let changed = match provider.token_status.as_deref() {
+            Some(previous) => previous != result.status.as_str(),
+            None => result.status != TokenStatus::Valid,
+        };
+        if changed {
+            changes.push(TokenStatusChange {
+                provider_id: provider.id,
+                name: provider.name,
+                previous: provider.token_status,
+                status: result.status,
+                http_status: result.http_status,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Periodically re-checks every stored token so revoked ones show up before the user
+/// switches to them.
+pub async fn monitor_tokens(app: AppHandle) {
+    let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+
+    loop {
+        ticker.tick().await;
+
+        let state = app.state::<AppState>();
+        let pool = match state.pool().await {
+            Ok(pool) => pool,
+            Err(_) => continue,
+        };
+        let key = state.vault_key().await;
+        if let Ok(changes) = check_tokens(&pool, key.as_ref()).await {
+            for change in changes {
+                let _ = app.emit(TOKEN_STATUS_CHANGED_EVENT, change);
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::error::AppError;
+use crate::models::{SwitchEvent, SwitchTrigger};
+use chrono::Utc;
+use sqlx::SqlitePool;
+
+/// The account OpenCode was last switched to, if any.
+pub async fn active_provider_id(pool: &SqlitePool) -> Result<Option<String>, AppError> {
+    let id: Option<Option<String>> = sqlx::query_scalar("SELECT provider_id FROM active_provider WHERE id = 1")
+        .fetch_optional(pool)
+        .await?;
+    Ok(id.flatten())
+}
+
+async fn provider_name(pool: &SqlitePool, id: Option<&str>) -> Result<Option<String>, AppError> {
+    let id = match id {
+        Some(id) => id,
+        None => return Ok(None),
+    };
+    let name = sqlx::query_scalar("SELECT name FROM github_providers WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(name)
+}
+
+/// Logs a switch away from the current active account and, if it succeeded, moves the
+/// active pointer to `to`.
+pub async fn record_switch(
+    pool: &SqlitePool,
+    to: Option<&str>,
+    trigger: SwitchTrigger,
+    error: Option<&str>,
+) -> Result<(), AppError> {
+    let from = active_provider_id(pool).await?;
+    // Names are copied so the history stays readable after an account is deleted
+    let from_name = provider_name(pool, from.as_deref()).await?;
+    let to_name = provider_name(pool, to).await?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO switch_events (from_provider_id, from_name, to_provider_id, to_name, created_at, triggered_by, outcome, error)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+    )
+    .bind(&from)
+    .bind(&from_name)
+    .bind(to)
+    .bind(&to_name)
+    .bind(Utc::now().timestamp())
+    .bind(trigger.as_str())
+    .bind(if error.is_none() { "success" } else { "failed" })
+    .bind(error)
+    .execute(&mut *tx)
+    .await?;
+    if error.is_none() {
+        sqlx::query("INSERT OR REPLACE INTO active_provider (id, provider_id) VALUES (1, ?)")
+            .bind(to)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn list_history(pool: &SqlitePool, limit: i64) -> Result<Vec<SwitchEvent>, AppError> {
+    let events = sqlx::query_as::<_, SwitchEvent>("SELECT * FROM switch_events ORDER BY id DESC LIMIT ?")
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+    Ok(events)
+}
+
+/// The account that was active before the current one, skipping accounts deleted since.
+pub async fn previous_provider_id(pool: &SqlitePool) -> Result<Option<String>, AppError> {
+    let active = active_provider_id(pool).await?;
+    let id = sqlx::query_scalar(
+        "SELECT e.from_provider_id FROM switch_events e
+         JOIN github_providers p ON p.id = e.from_provider_id
+         WHERE e.outcome = 'success' AND e.to_provider_id IS ? AND e.from_provider_id IS NOT ?
+         ORDER BY e.id DESC LIMIT 1"
+    )
+    .bind(&active)
+    .bind(&active)
+    .fetch_optional(pool)
+    .await?;
+    Ok(id)
+}
@@ -1,13 +1,26 @@
 pub mod error;
 pub mod models;
 pub mod database;
+pub mod migrations;
 pub mod github_oauth;
 pub mod auth_config;
+pub mod backup;
+pub mod reload;
+pub mod opencode_server;
 pub mod provider_service;
+pub mod settings;
+pub mod health;
+pub mod history;
+pub mod transfer;
+pub mod vault;
 pub mod commands;
 pub mod state;
+pub mod watcher;
+#[cfg(test)]
+mod test_support;
 
 use state::AppState;
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -16,15 +29,59 @@ pub fn run() {
         .manage(AppState::default())
         .invoke_handler(tauri::generate_handler![
             commands::list_providers,
+            commands::update_provider_metadata,
             commands::start_device_flow,
             commands::complete_device_flow,
             commands::delete_provider,
             commands::switch_provider,
+            commands::sign_out,
+            commands::switch_back,
+            commands::get_active_provider_id,
+            commands::list_switch_history,
             commands::open_url,
-            commands::sync_active_account
+            commands::sync_active_account,
+            commands::list_auth_snapshots,
+            commands::diff_auth_snapshot,
+            commands::restore_auth_snapshot,
+            commands::list_target_dirs,
+            commands::add_target_dir,
+            commands::remove_target_dir,
+            commands::set_target_dir_enabled,
+            commands::list_auth_entries,
+            commands::preview_normalize_auth,
+            commands::normalize_auth,
+            commands::inspect_auth_files,
+            commands::align_auth_files,
+            commands::vault_status,
+            commands::setup_vault,
+            commands::unlock_vault,
+            commands::lock_vault,
+            commands::change_vault_passphrase,
+            commands::export_accounts,
+            commands::import_accounts,
+            commands::merge_database,
+            commands::check_token_health,
+            commands::reauthorize_provider
         ])
-        .setup(|_app| {
-            // Setup logic will be initialized in commands/state when called
+        .setup(|app| {
+            // Background work: temp-file cleanup, the auth.json watcher and the token health monitor
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                // Custom directories live in the database, so wait for it before cleaning up
+                let pool = match handle.state::<AppState>().pool().await {
+                    Ok(pool) => pool,
+                    Err(_) => return,
+                };
+                if let Ok(targets) = settings::list_target_dirs(&pool).await {
+                    let _ = auth_config::run_blocking(move || {
+                        auth_config::cleanup_orphaned_temp_files(&targets);
+                        Ok(())
+                    })
+                    .await;
+                }
+            });
+            tauri::async_runtime::spawn(watcher::watch_auth_files(app.handle().clone()));
+            tauri::async_runtime::spawn(health::monitor_tokens(app.handle().clone()));
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -0,0 +1,332 @@
+use crate::error::AppError;
+use chrono::Utc;
+use sqlx::{SqliteConnection, SqlitePool};
+use std::path::Path;
+
+/// Ordered schema steps: entry `i` upgrades the database from version `i` to `i + 1`.
+/// Released steps must never be edited; append a new one instead.
+const MIGRATIONS: &[&[&str]] = &[
+    // 1: accounts
+    &["CREATE TABLE IF NOT EXISTS github_providers (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        access_token TEXT NOT NULL,
+        email TEXT,
+        avatar_url TEXT,
+        github_id INTEGER NOT NULL,
+        created_at INTEGER NOT NULL,
+        last_used_at INTEGER
+    )"],
+    // 2: user-configured OpenCode directories
+    &["CREATE TABLE IF NOT EXISTS target_dirs (
+        path TEXT PRIMARY KEY,
+        custom INTEGER NOT NULL,
+        enabled INTEGER NOT NULL
+    )"],
+    // 3: master passphrase salt and verifier for the token vault
+    &["CREATE TABLE IF NOT EXISTS vault (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        salt TEXT NOT NULL,
+        verifier TEXT NOT NULL
+    )"],
+    // 4: one row per GitHub account; merge duplicates created by re-authorizing, keeping the
+    // most recently used row's token and the earliest created_at
+    &[
+        "ALTER TABLE github_providers ADD COLUMN host TEXT NOT NULL DEFAULT 'github.com'",
+        "CREATE TEMP TABLE merged_providers AS
+            SELECT host, github_id, MIN(created_at) AS created_at, MAX(last_used_at) AS last_used_at
+            FROM github_providers GROUP BY host, github_id HAVING COUNT(*) > 1",
+        "DELETE FROM github_providers WHERE id NOT IN (
+            SELECT id FROM (
+                SELECT id, ROW_NUMBER() OVER (
+                    PARTITION BY host, github_id
+                    ORDER BY COALESCE(last_used_at, created_at) DESC, created_at DESC
+                ) AS rank
+                FROM github_providers
+            ) WHERE rank = 1
+        )",
+        "UPDATE github_providers SET
+            created_at = (SELECT m.created_at FROM merged_providers m WHERE m.host = github_providers.host AND m.github_id = github_providers.github_id),
+            last_used_at = (SELECT m.last_used_at FROM merged_providers m WHERE m.host = github_providers.host AND m.github_id = github_providers.github_id)
+        WHERE EXISTS (SELECT 1 FROM merged_providers m WHERE m.host = github_providers.host AND m.github_id = github_providers.github_id)",
+        "DROP TABLE merged_providers",
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_github_providers_host_github_id ON github_providers (host, github_id)",
+    ],
+    // 5: user-editable labels
+    &[
+        "ALTER TABLE github_providers ADD COLUMN display_name TEXT",
+        "ALTER TABLE github_providers ADD COLUMN notes TEXT",
+        "ALTER TABLE github_providers ADD COLUMN color TEXT",
+        "CREATE TABLE IF NOT EXISTS provider_tags (
+            provider_id TEXT NOT NULL REFERENCES github_providers(id) ON DELETE CASCADE,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (provider_id, tag)
+        )",
+    ],
+    // 6: explicit active account and switch history, seeded from last_used_at
+    &[
+        "CREATE TABLE IF NOT EXISTS active_provider (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            provider_id TEXT REFERENCES github_providers(id) ON DELETE SET NULL
+        )",
+        "INSERT OR IGNORE INTO active_provider (id, provider_id)
+            SELECT 1, id FROM github_providers WHERE last_used_at IS NOT NULL ORDER BY last_used_at DESC LIMIT 1",
+        "CREATE TABLE IF NOT EXISTS switch_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            from_provider_id TEXT,
+            from_name TEXT,
+            to_provider_id TEXT,
+            to_name TEXT,
+            created_at INTEGER NOT NULL,
+            triggered_by TEXT NOT NULL,
+            outcome TEXT NOT NULL,
+            error TEXT
+        )",
+    ],
+    // 7: results of the background token health check
+    &[
+        "ALTER TABLE github_providers ADD COLUMN last_validated_at INTEGER",
+        "ALTER TABLE github_providers ADD COLUMN token_status TEXT",
+        "ALTER TABLE github_providers ADD COLUMN last_http_status INTEGER",
+    ],
+];
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.len() as i64
+}
+
+pub async fn current_version(pool: &SqlitePool) -> Result<i64, AppError> {
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            applied_at INTEGER NOT NULL
+        )"
+    )
+    .execute(pool)
+    .await?;
+
+    let version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+        .fetch_one(pool)
+        .await?;
+    Ok(version.unwrap_or(0))
+}
+
+async fn has_table(conn: &mut SqliteConnection, table: &str) -> Result<bool, AppError> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+        .bind(table)
+        .fetch_one(conn)
+        .await?;
+    Ok(count > 0)
+}
+
+async fn has_column(conn: &mut SqliteConnection, table: &str, column: &str) -> Result<bool, AppError> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?")
+        .bind(table)
+        .bind(column)
+        .fetch_one(conn)
+        .await?;
+    Ok(count > 0)
+}
+
+// Databases created before versioning have the accounts table but possibly not avatar_url,
+// which used to be added with an unchecked ALTER TABLE on every start
+async fn adopt_unversioned_schema(conn: &mut SqliteConnection) -> Result<(), AppError> {
+    if has_table(conn, "github_providers").await? && !has_column(conn, "github_providers", "avatar_url").await? {
+        sqlx::query("ALTER TABLE github_providers ADD COLUMN avatar_url TEXT")
+            .execute(conn)
+            .await?;
+    }
+    Ok(())
+}
+
+const BACKUP_PREFIX: &str = "providers.db.v";
+const BACKUP_SUFFIX: &str = ".bak";
+
+/// Copies the database next to itself before it is migrated, using SQLite so the copy is consistent.
+async fn backup_database(pool: &SqlitePool, db_path: &Path, version: i64) -> Result<(), AppError> {
+    let backup_path = db_path.with_file_name(format!(
+        "{}{}-{}{}",
+        BACKUP_PREFIX,
+        version,
+        Utc::now().format("%Y%m%d-%H%M%S"),
+        BACKUP_SUFFIX
+    ));
+    sqlx::query("VACUUM INTO ?")
+        .bind(backup_path.to_string_lossy().into_owned())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Deletes the pre-migration copies of the database, e.g. once they would be the only place
+/// tokens are still stored unencrypted. Returns how many were removed.
+pub fn remove_backups(db_path: &Path) -> Result<usize, AppError> {
+    let dir = match db_path.parent() {
+        Some(dir) => dir,
+        None => return Ok(0),
+    };
+    let mut removed = 0;
+    for entry in std::fs::read_dir(dir)?.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with(BACKUP_PREFIX) && name.ends_with(BACKUP_SUFFIX) {
+            std::fs::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Brings the database up to the latest schema, one transaction per step.
+pub async fn run_migrations(pool: &SqlitePool, db_path: Option<&Path>) -> Result<(), AppError> {
+    apply_migrations(pool, db_path, MIGRATIONS).await
+}
+
+async fn apply_migrations(pool: &SqlitePool, db_path: Option<&Path>, migrations: &[&[&str]]) -> Result<(), AppError> {
+    let current = current_version(pool).await?;
+    let latest = migrations.len() as i64;
+    if current > latest {
+        return Err(AppError::SchemaTooNew {
+            found: current,
+            supported: latest,
+        });
+    }
+    if current == latest {
+        return Ok(());
+    }
+
+    // Throwaway databases have nothing worth backing up
+    if let Some(db_path) = db_path {
+        if current > 0 || has_table(&mut *pool.acquire().await?, "github_providers").await? {
+            backup_database(pool, db_path, current).await?;
+        }
+    }
+
+    for (index, statements) in migrations.iter().enumerate().skip(current as usize) {
+        let version = index as i64 + 1;
+        let mut tx = pool.begin().await?;
+        if version == 1 {
+            adopt_unversioned_schema(&mut tx).await?;
+        }
+        for statement in statements.iter() {
+            sqlx::query(statement).execute(&mut *tx).await?;
+        }
+        sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, ?)")
+            .bind(version)
+            .bind(Utc::now().timestamp())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+
+    // The accounts table as created before the schema was versioned
+    const BASELINE_SCHEMA: &str = "CREATE TABLE github_providers (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        access_token TEXT NOT NULL,
+        email TEXT,
+        github_id INTEGER NOT NULL,
+        created_at INTEGER NOT NULL,
+        last_used_at INTEGER
+    )";
+
+    async fn memory_pool() -> SqlitePool {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(SqliteConnectOptions::new().in_memory(true))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn duplicate_accounts_are_merged_into_one_row() {
+        let pool = memory_pool().await;
+        sqlx::query(BASELINE_SCHEMA).execute(&pool).await.unwrap();
+        sqlx::query(
+            "INSERT INTO github_providers (id, name, access_token, github_id, created_at, last_used_at) VALUES
+                ('a', 'octocat', 'old', 1, 10, 100),
+                ('b', 'octocat', 'newest', 1, 20, 300),
+                ('c', 'octocat', 'unused', 1, 5, NULL),
+                ('d', 'hubot', 'other', 2, 30, NULL)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        run_migrations(&pool, None).await.unwrap();
+
+        let rows: Vec<(String, String, i64, Option<i64>)> =
+            sqlx::query_as("SELECT id, access_token, created_at, last_used_at FROM github_providers ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                ("b".to_string(), "newest".to_string(), 5, Some(300)),
+                ("d".to_string(), "other".to_string(), 30, None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn unversioned_database_is_adopted_and_upgraded() {
+        let pool = memory_pool().await;
+        sqlx::query(BASELINE_SCHEMA).execute(&pool).await.unwrap();
+        sqlx::query("INSERT INTO github_providers (id, name, access_token, github_id, created_at) VALUES ('a', 'octocat', 't', 1, 0)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        run_migrations(&pool, None).await.unwrap();
+
+        assert_eq!(current_version(&pool).await.unwrap(), latest_version());
+        let mut conn = pool.acquire().await.unwrap();
+        assert!(has_column(&mut conn, "github_providers", "avatar_url").await.unwrap());
+        assert!(has_column(&mut conn, "github_providers", "token_status").await.unwrap());
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM github_providers")
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn newer_schema_is_refused() {
+        let pool = memory_pool().await;
+        run_migrations(&pool, None).await.unwrap();
+        sqlx::query("INSERT INTO schema_version (version, applied_at) VALUES (?, 0)")
+            .bind(latest_version() + 1)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let err = run_migrations(&pool, None).await.unwrap_err();
+
+        assert!(matches!(err, AppError::SchemaTooNew { found, supported } if found == latest_version() + 1 && supported == latest_version()));
+    }
+
+    #[tokio::test]
+    async fn failing_step_is_rolled_back() {
+        let pool = memory_pool().await;
+        let migrations: &[&[&str]] = &[
+            &["CREATE TABLE first (id INTEGER)"],
+            &["CREATE TABLE second (id INTEGER)", "INSERT INTO missing VALUES (1)"],
+        ];
+
+        assert!(apply_migrations(&pool, None, migrations).await.is_err());
+
+        assert_eq!(current_version(&pool).await.unwrap(), 1);
+        let mut conn = pool.acquire().await.unwrap();
+        assert!(has_table(&mut conn, "first").await.unwrap());
+        assert!(!has_table(&mut conn, "second").await.unwrap());
+    }
+}
@@ -1,21 +1,61 @@
+use crate::auth_config::AuthLayout;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct GitHubProvider {
     pub id: String,
+    pub host: String,
     pub name: String,
+    // Never leaves the Rust side; the webview gets a `ProviderSummary` instead
+    #[serde(skip_serializing)]
     pub access_token: String,
     pub email: Option<String>,
     pub avatar_url: Option<String>,
     pub github_id: i64,
     pub created_at: i64,
     pub last_used_at: Option<i64>,
+    pub display_name: Option<String>,
+    pub notes: Option<String>,
+    /// `#rrggbb`
+    pub color: Option<String>,
+    pub last_validated_at: Option<i64>,
+    /// "valid", "revoked" or "network-error"; `None` until first checked
+    pub token_status: Option<String>,
+    pub last_http_status: Option<i64>,
+    // Stored in provider_tags
+    #[sqlx(skip)]
+    pub tags: Vec<String>,
+}
+
+/// What the frontend sees of a provider: identifies the token without exposing it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderSummary {
+    pub id: String,
+    pub host: String,
+    pub name: String,
+    pub email: Option<String>,
+    pub avatar_url: Option<String>,
+    pub github_id: i64,
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+    pub display_name: Option<String>,
+    pub notes: Option<String>,
+    pub color: Option<String>,
+    pub tags: Vec<String>,
+    pub last_validated_at: Option<i64>,
+    pub token_status: Option<String>,
+    pub last_http_status: Option<i64>,
+    /// Last four characters of the token; `None` while the vault is locked.
+    pub token_last4: Option<String>,
+    /// First 8 hex digits of the token's SHA-256.
+    pub token_hash: Option<String>,
 }
 
 impl GitHubProvider {
     pub fn new(
         id: String,
+        host: String,
         name: String,
         access_token: String,
         email: Option<String>,
@@ -23,9 +63,17 @@ impl GitHubProvider {
         github_id: i64,
         created_at: i64,
         last_used_at: Option<i64>,
+        display_name: Option<String>,
+        notes: Option<String>,
+        color: Option<String>,
+        last_validated_at: Option<i64>,
+        token_status: Option<String>,
+        last_http_status: Option<i64>,
+        tags: Vec<String>,
     ) -> Self {
         Self {
             id,
+            host,
             name,
             access_token,
             email,
@@ -33,6 +81,13 @@ impl GitHubProvider {
             github_id,
             created_at,
             last_used_at,
+            display_name,
+            notes,
+            color,
+            last_validated_at,
+            token_status,
+            last_http_status,
+            tags,
         }
     }
 }
@@ -84,9 +139,205 @@ pub struct GitHubUser {
     pub avatar_url: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CopilotToken {
+    pub token: String,
+    /// Unix timestamp in seconds.
+    pub expires_at: i64,
+    pub refresh_in: Option<i64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitHubEmail {
     pub email: String,
     pub primary: bool,
     pub verified: bool,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalledProcess {
+    pub pid: u32,
+    pub command_line: String,
+    pub forced: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReloadReport {
+    pub method: String,
+    pub processes: Vec<SignalledProcess>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WriteStatus {
+    Written,
+    Skipped,
+    Failed,
+    /// Refused because the file could not be parsed; `backup` says where it was copied.
+    Corrupt,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetOutcome {
+    pub path: String,
+    pub status: WriteStatus,
+    pub error: Option<String>,
+    pub backup: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwitchOutcome {
+    /// OpenCode servers that accepted the credential over HTTP.
+    pub servers: Vec<String>,
+    /// Per-file results when auth.json had to be written directly.
+    pub targets: Vec<TargetOutcome>,
+    /// Set when no server answered and OpenCode had to be restarted instead.
+    pub reload: Option<ReloadReport>,
+    pub reload_error: Option<String>,
+    /// Why no Copilot token could be fetched, leaving OpenCode to exchange the OAuth token itself.
+    pub copilot_token_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthSnapshot {
+    pub id: String,
+    pub target_path: String,
+    pub created_at: i64,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetDir {
+    pub path: String,
+    /// Where the directory came from, e.g. "xdg-data" or "custom".
+    pub source: String,
+    pub custom: bool,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthEntrySummary {
+    pub path: String,
+    pub layout: AuthLayout,
+    pub provider: String,
+    pub entry_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizePreview {
+    pub path: String,
+    /// `None` when the file could not be read.
+    pub layout: Option<AuthLayout>,
+    pub diff: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthFileStatus {
+    pub path: String,
+    pub enabled: bool,
+    pub modified_at: Option<i64>,
+    pub github_id: Option<i64>,
+    pub login: Option<String>,
+    /// The stored provider the token belongs to, if any.
+    pub provider_id: Option<String>,
+    pub matches_active: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultStatus {
+    pub initialized: bool,
+    pub unlocked: bool,
+}
+
+/// User-editable labels for a provider; replaces all of them at once.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderMetadata {
+    pub display_name: Option<String>,
+    pub notes: Option<String>,
+    pub color: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// What caused a switch, as recorded in the history.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SwitchTrigger {
+    Ui,
+    Cli,
+    Rule,
+    Sync,
+}
+
+impl SwitchTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SwitchTrigger::Ui => "ui",
+            SwitchTrigger::Cli => "cli",
+            SwitchTrigger::Rule => "rule",
+            SwitchTrigger::Sync => "sync",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SwitchEvent {
+    pub id: i64,
+    /// `None` when nothing was active before.
+    pub from_provider_id: Option<String>,
+    pub from_name: Option<String>,
+    /// `None` for a sign-out.
+    pub to_provider_id: Option<String>,
+    pub to_name: Option<String>,
+    pub created_at: i64,
+    pub triggered_by: String,
+    /// "success" or "failed"
+    pub outcome: String,
+    pub error: Option<String>,
+}
+
+/// Display names of the accounts an import added, updated or left alone.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenStatus {
+    Valid,
+    Revoked,
+    NetworkError,
+}
+
+impl TokenStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenStatus::Valid => "valid",
+            TokenStatus::Revoked => "revoked",
+            TokenStatus::NetworkError => "network-error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenValidation {
+    pub status: TokenStatus,
+    /// `None` if no response was received.
+    pub http_status: Option<u16>,
+    pub user: Option<GitHubUser>,
+}
+
+/// Payload of the event emitted when a stored token starts or stops working.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenStatusChange {
+    pub provider_id: String,
+    pub name: String,
+    pub previous: Option<String>,
+    pub status: TokenStatus,
+    pub http_status: Option<u16>,
+}
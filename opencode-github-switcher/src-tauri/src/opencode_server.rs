@@ -0,0 +1,150 @@
+use crate::reload::detect_reloader;
+use reqwest::Client;
+use serde_json::Value;
+use std::time::Duration;
+
+const DEFAULT_SERVER_URL: &str = "http://127.0.0.1:4096";
+const DEFAULT_HOSTNAME: &str = "127.0.0.1";
+// Comma-separated list of extra server base URLs to try, e.g. a fake server in development
+const SERVER_URLS_ENV: &str = "OPENCODE_SWITCHER_SERVER_URLS";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+// The OpenAPI document every OpenCode server publishes; its title identifies the server
+const SPEC_PATH: &str = "/doc";
+const SPEC_TITLE: &str = "opencode";
+
+fn parse_flag<'a>(args: &[&'a str], flag: &str) -> Option<&'a str> {
+    let prefix = format!("{}=", flag);
+    args.iter().enumerate().find_map(|(i, arg)| {
+        if *arg == flag {
+            args.get(i + 1).copied()
+        } else {
+            arg.strip_prefix(prefix.as_str())
+        }
+    })
+}
+
+fn server_url_from_command(command_line: &str) -> Option<String> {
+    let args: Vec<&str> = command_line.split_whitespace().collect();
+    let port: u16 = parse_flag(&args, "--port")?.parse().ok()?;
+    // Port 0 lets OpenCode pick a random port we have no way to learn from the command line
+    if port == 0 {
+        return None;
+    }
+    let hostname = match parse_flag(&args, "--hostname") {
+        Some("0.0.0.0") | None => DEFAULT_HOSTNAME,
+        Some(h) => h,
+    };
+    Some(format!("http://{}:{}", hostname, port))
+}
+
+/// Base URLs of OpenCode servers that may be running locally.
+pub fn discover_servers() -> Vec<String> {
+    let mut urls: Vec<String> = std::env::var(SERVER_URLS_ENV)
+        .map(|v| {
+            v.split(',')
+                .map(|u| u.trim().trim_end_matches('/').to_string())
+                .filter(|u| !u.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if let Ok(processes) = detect_reloader().find_processes() {
+        urls.extend(processes.iter().filter_map(|(_, cmd)| server_url_from_command(cmd)));
+    }
+    urls.push(DEFAULT_SERVER_URL.to_string());
+
+    let mut seen = Vec::new();
+    urls.retain(|u| {
+        if seen.contains(u) {
+            false
+        } else {
+            seen.push(u.clone());
+            true
+        }
+    });
+    urls
+}
+
+/// Whether `base_url` is an OpenCode server rather than some other service that happens
+/// to listen there, which must never be sent a credential.
+async fn is_opencode_server(client: &Client, base_url: &str) -> bool {
+    let res = match client.get(format!("{}{}", base_url, SPEC_PATH)).send().await {
+        Ok(res) if res.status().is_success() => res,
+        _ => return false,
+    };
+    match res.json::<Value>().await {
+        Ok(spec) => spec
+            .pointer("/info/title")
+            .and_then(Value::as_str)
+            .map(|title| title.eq_ignore_ascii_case(SPEC_TITLE))
+            .unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+/// Sends the credential to every discovered server and returns the URLs that accepted it.
+pub async fn push_credential(provider_id: &str, credential: &Value) -> Vec<String> {
+    // Discovery lists processes with blocking calls
+    let urls = tokio::task::spawn_blocking(discover_servers).await.unwrap_or_default();
+    push_credential_to(&urls, provider_id, credential).await
+}
+
+async fn push_credential_to(urls: &[String], provider_id: &str, credential: &Value) -> Vec<String> {
+    let client = match Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(c) => c,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut accepted = Vec::new();
+    for base_url in urls {
+        if !is_opencode_server(&client, base_url).await {
+            continue;
+        }
+        let res = client
+            .put(format!("{}/auth/{}", base_url, provider_id))
+            .json(credential)
+            .send()
+            .await;
+        if let Ok(res) = res {
+            if res.status().is_success() {
+                accepted.push(base_url.clone());
+            }
+        }
+    }
+    accepted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::spawn_server;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn pushes_the_credential_to_an_opencode_server() {
+        let (url, requests) = spawn_server(|_, path| match path {
+            "/doc" => (200, r#"{"openapi":"3.1.0","info":{"title":"opencode","version":"1.0.0"}}"#.to_string()),
+            _ => (200, "true".to_string()),
+        })
+        .await;
+        let credential = json!({ "type": "oauth", "refresh": "gho_abc", "access": "tid=1", "expires": 0 });
+
+        let accepted = push_credential_to(std::slice::from_ref(&url), "github-copilot", &credential).await;
+
+        assert_eq!(accepted, vec![url]);
+        let requests = requests.lock().unwrap();
+        let put = requests.iter().find(|r| r.method == "PUT").expect("no PUT was sent");
+        assert_eq!(put.path, "/auth/github-copilot");
+        assert_eq!(serde_json::from_str::<Value>(&put.body).unwrap(), credential);
+    }
+
+    #[tokio::test]
+    async fn leaves_other_local_services_alone() {
+        let (url, requests) = spawn_server(|_, _| (200, "{}".to_string())).await;
+
+        let accepted = push_credential_to(&[url], "github-copilot", &json!({ "type": "oauth" })).await;
+
+        assert!(accepted.is_empty());
+        assert!(requests.lock().unwrap().iter().all(|r| r.method != "PUT"));
+    }
+}
@@ -1,16 +1,251 @@
-use crate::auth_config::{update_auth_json, read_current_token};
+use crate::auth_config::{
+    copilot_credential, copilot_oauth_token, read_auth_file, read_current_token, remove_copilot_entries, run_blocking, update_auth_json,
+    COPILOT_PROVIDER_ID,
+};
 use crate::error::AppError;
-use crate::github_oauth::{get_device_code, get_user_info, poll_for_token};
-use crate::models::{DeviceCodeResponse, GitHubProvider};
+use crate::github_oauth::{get_copilot_token, get_device_code, get_user_info, poll_for_token, validate_token, GITHUB_HOST};
+use crate::health::record_validation;
+use crate::history::{active_provider_id, previous_provider_id, record_switch};
+use crate::models::{
+    AuthFileStatus, DeviceCodeResponse, GitHubProvider, GitHubUser, ProviderMetadata, ProviderSummary, ReloadReport, SwitchOutcome, SwitchTrigger,
+    TokenStatus, WriteStatus,
+};
+use crate::opencode_server::push_credential;
+use crate::reload::reload_opencode;
+use crate::settings::list_target_dirs;
+use crate::vault::{ensure_unlocked, fingerprint, open_token, seal_token, VaultKey};
 use chrono::Utc;
 use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
 use uuid::Uuid;
 
-pub async fn list_providers(pool: &SqlitePool) -> Result<Vec<GitHubProvider>, AppError> {
-    let providers = sqlx::query_as::<_, GitHubProvider>("SELECT * FROM github_providers ORDER BY created_at DESC")
+// Kept short so a switch never hangs on a slow network; `force` skips the check entirely
+const PRE_SWITCH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// All providers, or only those carrying `tag`, compared the way tags are stored.
+pub async fn list_providers(pool: &SqlitePool, tag: Option<&str>) -> Result<Vec<GitHubProvider>, AppError> {
+    let mut providers = match tag.map(normalize_tag).filter(|t| !t.is_empty()) {
+        Some(tag) => {
+            sqlx::query_as::<_, GitHubProvider>(
+                "SELECT p.* FROM github_providers p
+                 JOIN provider_tags t ON t.provider_id = p.id
+                 WHERE t.tag = ?
+                 ORDER BY p.created_at DESC"
+            )
+            .bind(&tag)
+            .fetch_all(pool)
+            .await?
+        }
+        None => {
+            sqlx::query_as::<_, GitHubProvider>("SELECT * FROM github_providers ORDER BY created_at DESC")
+                .fetch_all(pool)
+                .await?
+        }
+    };
+    load_tags(pool, &mut providers).await?;
+    Ok(providers)
+}
+
+async fn load_tags(pool: &SqlitePool, providers: &mut [GitHubProvider]) -> Result<(), AppError> {
+    let rows = sqlx::query_as::<_, (String, String)>("SELECT provider_id, tag FROM provider_tags ORDER BY tag")
         .fetch_all(pool)
         .await?;
-    Ok(providers)
+    for provider in providers.iter_mut() {
+        provider.tags = rows
+            .iter()
+            .filter(|(id, _)| *id == provider.id)
+            .map(|(_, tag)| tag.clone())
+            .collect();
+    }
+    Ok(())
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Replaces the provider's display name, notes, color and tags.
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// Tags as they are stored: trimmed, lowercase, sorted and without duplicates or blanks.
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .iter()
+        .map(|t| normalize_tag(t))
+        .filter(|t| !t.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+pub async fn update_provider_metadata(
+    pool: &SqlitePool,
+    id: &str,
+    metadata: ProviderMetadata,
+) -> Result<GitHubProvider, AppError> {
+    let color = non_empty(metadata.color);
+    if let Some(color) = &color {
+        let valid = color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+        if !valid {
+            return Err(AppError::InvalidInput(format!("color must look like #rrggbb, got {}", color)));
+        }
+    }
+    let tags = normalize_tags(&metadata.tags);
+
+    let mut tx = pool.begin().await?;
+    let updated = sqlx::query("UPDATE github_providers SET display_name = ?, notes = ?, color = ? WHERE id = ?")
+        .bind(non_empty(metadata.display_name))
+        .bind(non_empty(metadata.notes))
+        .bind(&color)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    if updated.rows_affected() == 0 {
+        return Err(AppError::ProviderNotFound(id.to_string()));
+    }
+    sqlx::query("DELETE FROM provider_tags WHERE provider_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    for tag in &tags {
+        sqlx::query("INSERT INTO provider_tags (provider_id, tag) VALUES (?, ?)")
+            .bind(id)
+            .bind(tag)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    let mut provider = sqlx::query_as::<_, GitHubProvider>("SELECT * FROM github_providers WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+    provider.tags = tags;
+    Ok(provider)
+}
+
+/// The frontend view of a provider, fingerprinting the token if it can be decrypted.
+pub fn summarize(provider: &GitHubProvider, key: Option<&VaultKey>) -> ProviderSummary {
+    let (token_last4, token_hash) = match open_token(key, &provider.access_token) {
+        Ok(token) => {
+            let (last4, hash) = fingerprint(&token);
+            (Some(last4), Some(hash))
+        }
+        Err(_) => (None, None),
+    };
+
+    ProviderSummary {
+        id: provider.id.clone(),
+        host: provider.host.clone(),
+        name: provider.name.clone(),
+        email: provider.email.clone(),
+        avatar_url: provider.avatar_url.clone(),
+        github_id: provider.github_id,
+        created_at: provider.created_at,
+        last_used_at: provider.last_used_at,
+        display_name: provider.display_name.clone(),
+        notes: provider.notes.clone(),
+        color: provider.color.clone(),
+        tags: provider.tags.clone(),
+        last_validated_at: provider.last_validated_at,
+        token_status: provider.token_status.clone(),
+        last_http_status: provider.last_http_status,
+        token_last4,
+        token_hash,
+    }
+}
+
+/// Hands the token to OpenCode. With `always_write_files`, every auth.json is rewritten even
+/// when a running server accepted the credential.
+async fn activate_token(
+    pool: &SqlitePool,
+    access_token: &str,
+    username: &str,
+    always_write_files: bool,
+) -> Result<SwitchOutcome, AppError> {
+    // Hand OpenCode a ready-to-use Copilot token; if the exchange fails it can still fetch one
+    // itself, but the user is told why the credential went out without one
+    let (copilot_token, copilot_token_error) = match get_copilot_token(access_token).await {
+        Ok(token) => (Some(token), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let credential = copilot_credential(access_token, copilot_token.as_ref());
+
+    // Prefer hot-swapping through running OpenCode servers so in-flight sessions survive
+    let servers = push_credential(COPILOT_PROVIDER_ID, &credential.to_value()).await;
+    if !servers.is_empty() && !always_write_files {
+        return Ok(SwitchOutcome {
+            servers,
+            targets: Vec::new(),
+            reload: None,
+            reload_error: None,
+            copilot_token_error,
+        });
+    }
+
+    // No server answered: write auth.json and restart OpenCode so it picks up the new credential
+    let target_dirs = list_target_dirs(pool).await?;
+    let targets = {
+        let (credential, username) = (credential.clone(), username.to_string());
+        run_blocking(move || update_auth_json(&target_dirs, &credential, &username)).await?
+    };
+    if !servers.is_empty() {
+        return Ok(SwitchOutcome {
+            servers,
+            targets,
+            reload: None,
+            reload_error: None,
+            copilot_token_error,
+        });
+    }
+    let (reload, reload_error) = reload().await;
+
+    Ok(SwitchOutcome {
+        servers,
+        targets,
+        reload,
+        reload_error,
+        copilot_token_error,
+    })
+}
+
+async fn reload() -> (Option<ReloadReport>, Option<String>) {
+    match tokio::task::spawn_blocking(reload_opencode).await {
+        Ok(Ok(report)) => (Some(report), None),
+        Ok(Err(e)) => (None, Some(e.to_string())),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+/// Removes the GitHub Copilot credential from every auth.json and restarts OpenCode.
+pub async fn sign_out(pool: &SqlitePool) -> Result<SwitchOutcome, AppError> {
+    let target_dirs = list_target_dirs(pool).await?;
+    let targets = run_blocking(move || remove_copilot_entries(&target_dirs)).await?;
+    // Nothing was removed, so there is nothing for OpenCode to forget
+    if targets.iter().all(|t| t.status == WriteStatus::Skipped) {
+        return Ok(SwitchOutcome {
+            servers: Vec::new(),
+            targets,
+            reload: None,
+            reload_error: None,
+            copilot_token_error: None,
+        });
+    }
+    record_switch(pool, None, SwitchTrigger::Ui, None).await?;
+    let (reload, reload_error) = reload().await;
+
+    Ok(SwitchOutcome {
+        servers: Vec::new(),
+        targets,
+        reload,
+        reload_error,
+        copilot_token_error: None,
+    })
 }
 
 pub async fn start_oauth_flow(client_id: &str) -> Result<DeviceCodeResponse, AppError> {
@@ -19,66 +254,159 @@ pub async fn start_oauth_flow(client_id: &str) -> Result<DeviceCodeResponse, App
 
 pub async fn complete_oauth_flow(
     pool: &SqlitePool,
+    key: Option<&VaultKey>,
     client_id: &str,
     device_code: &str,
     interval: u64,
     expires_in: u64,
 ) -> Result<GitHubProvider, AppError> {
+    // Fail before the user authorizes rather than losing the token afterwards
+    ensure_unlocked(pool, key).await?;
     let access_token = poll_for_token(client_id, device_code, interval, expires_in).await?;
     let user_info = get_user_info(&access_token).await?;
 
-    let now = Utc::now().timestamp();
-    let id = Uuid::new_v4().to_string();
-
-    let provider = GitHubProvider {
-        id: id.clone(),
-        name: user_info.login,
-        access_token,
-        email: user_info.email,
-        avatar_url: user_info.avatar_url,
-        github_id: user_info.id,
-        created_at: now,
-        last_used_at: Some(now),
-    };
+    // Re-authorizing a known account refreshes its row instead of adding a duplicate
+    let provider = upsert_provider(pool, key, &user_info, &access_token).await?;
+
+    // Also activate the new account immediately
+    let result = activate_token(pool, &access_token, &provider.name, false).await;
+    let error = result.as_ref().err().map(|e| e.to_string());
+    record_switch(pool, Some(&provider.id), SwitchTrigger::Ui, error.as_deref()).await?;
+    result?;
+
+    Ok(provider)
+}
+
+/// Runs the device flow for an existing account and replaces its token, keeping its labels
+/// and history. The new token must belong to the same GitHub user.
+pub async fn reauthorize_provider(
+    pool: &SqlitePool,
+    key: Option<&VaultKey>,
+    id: &str,
+    client_id: &str,
+    device_code: &str,
+    interval: u64,
+    expires_in: u64,
+) -> Result<GitHubProvider, AppError> {
+    let provider = sqlx::query_as::<_, GitHubProvider>("SELECT * FROM github_providers WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::ProviderNotFound(id.to_string()))?;
+    // The device flow only issues github.com tokens
+    if provider.host != GITHUB_HOST {
+        return Err(AppError::UnsupportedHost {
+            name: provider.name,
+            host: provider.host,
+            supported: GITHUB_HOST.to_string(),
+        });
+    }
+    ensure_unlocked(pool, key).await?;
 
+    let access_token = poll_for_token(client_id, device_code, interval, expires_in).await?;
+    let user_info = get_user_info(&access_token).await?;
+    if user_info.id != provider.github_id {
+        return Err(AppError::AccountMismatch {
+            expected: provider.name,
+            found: user_info.login,
+        });
+    }
+
+    let now = Utc::now().timestamp();
     sqlx::query(
-        "INSERT INTO github_providers (id, name, access_token, email, avatar_url, github_id, created_at, last_used_at) 
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        "UPDATE github_providers SET access_token = ?, name = ?, email = ?, avatar_url = ?,
+             last_validated_at = ?, token_status = ?, last_http_status = NULL
+         WHERE id = ?"
     )
-    .bind(&provider.id)
-    .bind(&provider.name)
-    .bind(&provider.access_token)
-    .bind(&provider.email)
-    .bind(&provider.avatar_url)
-    .bind(provider.github_id)
-    .bind(provider.created_at)
-    .bind(provider.last_used_at)
+    .bind(seal_token(pool, key, &access_token).await?)
+    .bind(&user_info.login)
+    .bind(&user_info.email)
+    .bind(&user_info.avatar_url)
+    .bind(now)
+    .bind(TokenStatus::Valid.as_str())
+    .bind(id)
     .execute(pool)
     .await?;
 
-    // Also update auth.json immediately
-    update_auth_json(&provider.access_token, &provider.name)?;
+    // OpenCode is still using the old token if this is the active account. Logged like any
+    // other switch so a failed rewrite shows up in the history.
+    if active_provider_id(pool).await?.as_deref() == Some(id) {
+        let result = activate_token(pool, &access_token, &user_info.login, false).await;
+        let error = result.as_ref().err().map(|e| e.to_string());
+        record_switch(pool, Some(id), SwitchTrigger::Ui, error.as_deref()).await?;
+        result?;
+    }
 
+    let mut provider = sqlx::query_as::<_, GitHubProvider>("SELECT * FROM github_providers WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+    load_tags(pool, std::slice::from_mut(&mut provider)).await?;
     Ok(provider)
 }
 
-pub async fn delete_provider(pool: &SqlitePool, id: &str) -> Result<(), AppError> {
+/// Deletes the provider and returns whether it was the active account, in which case its
+/// token is still in auth.json until the caller signs out.
+pub async fn delete_provider(pool: &SqlitePool, id: &str) -> Result<bool, AppError> {
+    let was_active = active_provider_id(pool).await?.as_deref() == Some(id);
     sqlx::query("DELETE FROM github_providers WHERE id = ?")
         .bind(id)
         .execute(pool)
         .await?;
-    Ok(())
+    Ok(was_active)
+}
+
+pub async fn switch_provider(
+    pool: &SqlitePool,
+    key: Option<&VaultKey>,
+    id: &str,
+    trigger: SwitchTrigger,
+    force: bool,
+) -> Result<SwitchOutcome, AppError> {
+    switch_to(pool, key, id, trigger, false, !force).await
+}
+
+/// Switches to whichever account was active before the current one.
+pub async fn switch_back(pool: &SqlitePool, key: Option<&VaultKey>, force: bool) -> Result<SwitchOutcome, AppError> {
+    let id = previous_provider_id(pool)
+        .await?
+        .ok_or_else(|| AppError::InvalidInput("There is no previous account to switch back to".to_string()))?;
+    switch_to(pool, key, &id, SwitchTrigger::Ui, false, !force).await
+}
+
+/// Rewrites every auth.json with the given provider's credential so all directories agree.
+pub async fn align_auth_files(pool: &SqlitePool, key: Option<&VaultKey>, id: &str) -> Result<SwitchOutcome, AppError> {
+    switch_to(pool, key, id, SwitchTrigger::Ui, true, false).await
 }
 
-pub async fn switch_provider(pool: &SqlitePool, id: &str) -> Result<(), AppError> {
+async fn switch_to(
+    pool: &SqlitePool,
+    key: Option<&VaultKey>,
+    id: &str,
+    trigger: SwitchTrigger,
+    always_write_files: bool,
+    validate: bool,
+) -> Result<SwitchOutcome, AppError> {
     let provider = sqlx::query_as::<_, GitHubProvider>("SELECT * FROM github_providers WHERE id = ?")
         .bind(id)
         .fetch_optional(pool)
         .await?
         .ok_or_else(|| AppError::ProviderNotFound(id.to_string()))?;
 
-    // Update auth.json
-    update_auth_json(&provider.access_token, &provider.name)?;
+    let access_token = open_token(key, &provider.access_token)?;
+    let checked = if validate {
+        check_before_switch(pool, &provider, &access_token).await
+    } else {
+        Ok(())
+    };
+    // A rejected token is logged as a failed switch like any other error
+    let result = match checked {
+        Ok(()) => activate_token(pool, &access_token, &provider.name, always_write_files).await,
+        Err(e) => Err(e),
+    };
+    let error = result.as_ref().err().map(|e| e.to_string());
+    record_switch(pool, Some(id), trigger, error.as_deref()).await?;
+    let outcome = result?;
 
     // Update last_used_at
     let now = Utc::now().timestamp();
@@ -88,55 +416,178 @@ pub async fn switch_provider(pool: &SqlitePool, id: &str) -> Result<(), AppError
         .execute(pool)
         .await?;
 
-    Ok(())
+    Ok(outcome)
 }
 
-pub async fn sync_active_account(pool: &SqlitePool) -> Result<(), AppError> {
-    if let Some(token) = read_current_token() {
-        // Try to fetch user info to verify token and get details
-        if let Ok(user_info) = get_user_info(&token).await {
-            let now = Utc::now().timestamp();
-            
-            // Check if this provider already exists
-            let existing: Option<GitHubProvider> = sqlx::query_as(
-                "SELECT * FROM github_providers WHERE github_id = ?"
-            )
-            .bind(user_info.id)
-            .fetch_optional(pool)
-            .await?;
+/// Makes sure GitHub still accepts the token before anything is written or OpenCode is
+/// restarted; a dead token would otherwise leave the editor logged out.
+async fn check_before_switch(pool: &SqlitePool, provider: &GitHubProvider, access_token: &str) -> Result<(), AppError> {
+    if provider.host != GITHUB_HOST {
+        return Ok(());
+    }
+    let result = validate_token(access_token, PRE_SWITCH_CHECK_TIMEOUT).await;
+    record_validation(pool, &provider.id, &result).await?;
+    match result.status {
+        TokenStatus::Valid => Ok(()),
+        TokenStatus::Revoked => Err(AppError::TokenRejected {
+            name: provider.name.clone(),
+            status: result.http_status.unwrap_or_default(),
+        }),
+        TokenStatus::NetworkError => Err(AppError::TokenUnverified {
+            name: provider.name.clone(),
+        }),
+    }
+}
+
+/// Imports whatever account OpenCode is currently logged into and returns it.
+pub async fn sync_active_account(pool: &SqlitePool, key: Option<&VaultKey>) -> Result<Option<GitHubProvider>, AppError> {
+    let targets = list_target_dirs(pool).await?;
+    let token = match read_current_token(&targets) {
+        Some(token) => token,
+        None => return Ok(None),
+    };
+    // Try to fetch user info to verify token and get details
+    let user_info = match get_user_info(&token).await {
+        Ok(user_info) => user_info,
+        Err(_) => return Ok(None),
+    };
+    let provider = upsert_provider(pool, key, &user_info, &token).await?;
+    // OpenCode was switched outside the app, e.g. by `opencode auth login`
+    if active_provider_id(pool).await?.as_deref() != Some(provider.id.as_str()) {
+        record_switch(pool, Some(&provider.id), SwitchTrigger::Sync, None).await?;
+    }
+    Ok(Some(provider))
+}
+
+/// Inserts the account, or updates the token and profile of the existing row for the same
+/// GitHub user, keeping its id and metadata. The recorded health is cleared only if the token
+/// changed, since OpenCode rewrites auth.json with the same token on every Copilot refresh.
+/// Either way it becomes the most recently used.
+async fn upsert_provider(
+    pool: &SqlitePool,
+    key: Option<&VaultKey>,
+    user_info: &GitHubUser,
+    token: &str,
+) -> Result<GitHubProvider, AppError> {
+    let stored_token = seal_token(pool, key, token).await?;
+    let existing: Option<String> = sqlx::query_scalar("SELECT access_token FROM github_providers WHERE host = ? AND github_id = ?")
+        .bind(GITHUB_HOST)
+        .bind(user_info.id)
+        .fetch_optional(pool)
+        .await?;
+    let token_changed = match existing {
+        Some(stored) => open_token(key, &stored).ok().as_deref() != Some(token),
+        None => true,
+    };
+
+    let now = Utc::now().timestamp();
+    sqlx::query(
+        "INSERT INTO github_providers (id, host, name, access_token, email, avatar_url, github_id, created_at, last_used_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT (host, github_id) DO UPDATE SET
+             name = excluded.name,
+             access_token = excluded.access_token,
+             email = excluded.email,
+             avatar_url = excluded.avatar_url,
+             last_used_at = excluded.last_used_at,
+             last_validated_at = CASE WHEN ?10 THEN NULL ELSE last_validated_at END,
+             token_status = CASE WHEN ?10 THEN NULL ELSE token_status END,
+             last_http_status = CASE WHEN ?10 THEN NULL ELSE last_http_status END"
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(GITHUB_HOST)
+    .bind(&user_info.login)
+    .bind(&stored_token)
+    .bind(&user_info.email)
+    .bind(&user_info.avatar_url)
+    .bind(user_info.id)
+    .bind(now)
+    .bind(now)
+    .bind(token_changed)
+    .execute(pool)
+    .await?;
+
+    let mut provider = sqlx::query_as::<_, GitHubProvider>("SELECT * FROM github_providers WHERE host = ? AND github_id = ?")
+        .bind(GITHUB_HOST)
+        .bind(user_info.id)
+        .fetch_one(pool)
+        .await?;
+    load_tags(pool, std::slice::from_mut(&mut provider)).await?;
+    Ok(provider)
+}
 
-            if let Some(mut provider) = existing {
-                // Update token and last used time if it already exists
-                sqlx::query(
-                    "UPDATE github_providers SET access_token = ?, name = ?, email = ?, avatar_url = ?, last_used_at = ? WHERE github_id = ?"
-                )
-                .bind(&token)
-                .bind(&user_info.login)
-                .bind(&user_info.email)
-                .bind(&user_info.avatar_url)
-                .bind(now)
-                .bind(user_info.id)
-                .execute(pool)
-                .await?;
-            } else {
-                // Insert new provider if it doesn't exist
-                let id = Uuid::new_v4().to_string();
-                sqlx::query(
-                    "INSERT INTO github_providers (id, name, access_token, email, avatar_url, github_id, created_at, last_used_at) 
-                     VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
-                )
-                .bind(&id)
-                .bind(&user_info.login)
-                .bind(&token)
-                .bind(&user_info.email)
-                .bind(&user_info.avatar_url)
-                .bind(user_info.id)
-                .bind(now)
-                .bind(now)
-                .execute(pool)
-                .await?;
+async fn active_provider(pool: &SqlitePool) -> Result<Option<GitHubProvider>, AppError> {
+    let provider = sqlx::query_as::<_, GitHubProvider>(
+        "SELECT p.* FROM github_providers p JOIN active_provider a ON a.provider_id = p.id"
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(provider)
+}
+
+/// Every auth.json on disk, which GitHub account its Copilot token belongs to, and whether
+/// that is the account the switcher considers active.
+pub async fn inspect_auth_files(pool: &SqlitePool) -> Result<Vec<AuthFileStatus>, AppError> {
+    let targets = list_target_dirs(pool).await?;
+    let providers = list_providers(pool, None).await?;
+    let active = active_provider(pool).await?;
+    // Several files usually share a token; only ask GitHub once per token
+    let mut users: HashMap<String, Result<GitHubUser, String>> = HashMap::new();
+    let mut statuses = Vec::new();
+
+    for target in &targets {
+        let path = Path::new(&target.path).join("auth.json");
+        let metadata = match std::fs::metadata(&path) {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        let mut status = AuthFileStatus {
+            path: path.to_string_lossy().into_owned(),
+            enabled: target.enabled,
+            modified_at: metadata
+                .modified()
+                .ok()
+                .map(|t| chrono::DateTime::<Utc>::from(t).timestamp()),
+            github_id: None,
+            login: None,
+            provider_id: None,
+            matches_active: false,
+            error: None,
+        };
+
+        let token = match read_auth_file(&path) {
+            Ok(auth) => copilot_oauth_token(&auth),
+            Err(e) => {
+                status.error = Some(e.to_string());
+                statuses.push(status);
+                continue;
+            }
+        };
+        let token = match token {
+            Some(token) => token,
+            None => {
+                statuses.push(status);
+                continue;
             }
+        };
+
+        if !users.contains_key(&token) {
+            let user = get_user_info(&token).await.map_err(|e| e.to_string());
+            users.insert(token.clone(), user);
+        }
+        match &users[&token] {
+            Ok(user) => {
+                status.github_id = Some(user.id);
+                status.login = Some(user.login.clone());
+                status.provider_id = providers
+                    .iter()
+                    .find(|p| p.github_id == user.id)
+                    .map(|p| p.id.clone());
+                status.matches_active = active.as_ref().map(|a| a.github_id == user.id).unwrap_or(false);
+            }
+            Err(e) => status.error = Some(e.clone()),
         }
+        statuses.push(status);
     }
-    Ok(())
+    Ok(statuses)
 }
@@ -0,0 +1,340 @@
+use crate::error::AppError;
+use crate::models::{ReloadReport, SignalledProcess};
+use std::fs;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+const TERMINATE_TIMEOUT: Duration = Duration::from_secs(3);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const OPENCODE_BINARY: &str = "opencode";
+const OPENCODE_RUNTIMES: [&str; 2] = ["node", "bun"];
+// npm package whose `bin/opencode` launcher node or bun may be running
+const OPENCODE_PACKAGE_DIR: &str = "/opencode-ai/";
+
+/// Makes running OpenCode instances pick up a freshly written auth.json.
+pub trait Reloader {
+    fn name(&self) -> &'static str;
+    /// Running OpenCode processes as (pid, command line) pairs.
+    fn find_processes(&self) -> Result<Vec<(u32, String)>, AppError>;
+    fn terminate(&self, processes: Vec<(u32, String)>) -> Result<Vec<SignalledProcess>, AppError>;
+
+    fn reload(&self) -> Result<Vec<SignalledProcess>, AppError> {
+        let processes = self.find_processes()?;
+        self.terminate(processes)
+    }
+}
+
+pub struct LinuxReloader;
+pub struct MacReloader;
+pub struct WindowsReloader;
+pub struct NoopReloader;
+
+pub fn detect_reloader() -> Box<dyn Reloader + Send + Sync> {
+    match std::env::consts::OS {
+        "linux" => Box::new(LinuxReloader),
+        "macos" => Box::new(MacReloader),
+        "windows" => Box::new(WindowsReloader),
+        _ => Box::new(NoopReloader),
+    }
+}
+
+pub fn reload_opencode() -> Result<ReloadReport, AppError> {
+    let reloader = detect_reloader();
+    let processes = reloader.reload()?;
+    Ok(ReloadReport {
+        method: reloader.name().to_string(),
+        processes,
+    })
+}
+
+fn program_name(path: &str) -> String {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path).to_lowercase();
+    name.trim_end_matches(".exe").trim_end_matches(".js").to_string()
+}
+
+/// Whether `args` runs OpenCode itself: the `opencode` binary, or node/bun running the
+/// launcher from the opencode-ai package. Matching on the entrypoint rather than on
+/// "opencode" anywhere in the command line leaves unrelated tooling alone, such as a dev
+/// server started from a directory with "opencode" in its path.
+fn is_opencode_command(args: &[String]) -> bool {
+    let program = match args.first() {
+        Some(argv0) => program_name(argv0),
+        None => return false,
+    };
+    if program == OPENCODE_BINARY {
+        return true;
+    }
+    if !OPENCODE_RUNTIMES.contains(&program.as_str()) {
+        return false;
+    }
+    // The script is the first argument that is not a runtime flag, e.g. `bun run <script>`
+    match args[1..].iter().find(|a| !a.starts_with('-') && a.as_str() != "run") {
+        Some(script) => {
+            program_name(script) == OPENCODE_BINARY
+                || script.replace('\\', "/").to_lowercase().contains(OPENCODE_PACKAGE_DIR)
+        }
+        None => false,
+    }
+}
+
+/// Splits a command line on whitespace, keeping double-quoted paths together.
+fn split_command_line(command_line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in command_line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    args.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+    args
+}
+
+impl Reloader for LinuxReloader {
+    fn name(&self) -> &'static str {
+        "linux-signal"
+    }
+
+    fn find_processes(&self) -> Result<Vec<(u32, String)>, AppError> {
+        let own_pid = std::process::id();
+        let mut candidates = Vec::new();
+
+        for entry in fs::read_dir("/proc")? {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+            let pid = match entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) {
+                Some(pid) if pid != own_pid => pid,
+                _ => continue,
+            };
+            // Processes may exit or be inaccessible while we scan
+            let raw = match fs::read(entry.path().join("cmdline")) {
+                Ok(raw) if !raw.is_empty() => raw,
+                _ => continue,
+            };
+            let args: Vec<String> = raw
+                .split(|b| *b == 0)
+                .filter(|a| !a.is_empty())
+                .map(|a| String::from_utf8_lossy(a).into_owned())
+                .collect();
+            if is_opencode_command(&args) {
+                candidates.push((pid, args.join(" ")));
+            }
+        }
+
+        Ok(candidates)
+    }
+
+    fn terminate(&self, processes: Vec<(u32, String)>) -> Result<Vec<SignalledProcess>, AppError> {
+        terminate_unix(processes)
+    }
+}
+
+impl Reloader for MacReloader {
+    fn name(&self) -> &'static str {
+        "macos-signal"
+    }
+
+    fn find_processes(&self) -> Result<Vec<(u32, String)>, AppError> {
+        let own_pid = std::process::id();
+        let output = Command::new("ps").args(["-axo", "pid=,command="]).output()?;
+        if !output.status.success() {
+            return Err(AppError::System(format!("ps exited with {}", output.status)));
+        }
+
+        let mut candidates = Vec::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let line = line.trim_start();
+            let (pid, command_line) = match line.split_once(' ') {
+                Some((pid, rest)) => (pid, rest.trim()),
+                None => continue,
+            };
+            let pid = match pid.parse::<u32>() {
+                Ok(pid) if pid != own_pid => pid,
+                _ => continue,
+            };
+            if is_opencode_command(&split_command_line(command_line)) {
+                candidates.push((pid, command_line.to_string()));
+            }
+        }
+
+        Ok(candidates)
+    }
+
+    fn terminate(&self, processes: Vec<(u32, String)>) -> Result<Vec<SignalledProcess>, AppError> {
+        terminate_unix(processes)
+    }
+}
+
+impl Reloader for WindowsReloader {
+    fn name(&self) -> &'static str {
+        "windows-cim"
+    }
+
+    fn find_processes(&self) -> Result<Vec<(u32, String)>, AppError> {
+        // Emit "pid<TAB>commandline" for every node, bun or opencode process; which of them
+        // are OpenCode is decided below
+        let output = Command::new("powershell")
+            .args([
+                "-WindowStyle", "Hidden",
+                "-NoProfile",
+                "-Command",
+                "Get-CimInstance Win32_Process | Where-Object { @('node.exe','bun.exe','opencode.exe') -contains $_.Name } | ForEach-Object { \"$($_.ProcessId)`t$($_.CommandLine)\" }"
+            ])
+            .stdin(Stdio::null())
+            .output()?;
+        if !output.status.success() {
+            return Err(AppError::System(format!("powershell exited with {}", output.status)));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (pid, command_line) = line.split_once('\t')?;
+                let command_line = command_line.trim();
+                if !is_opencode_command(&split_command_line(command_line)) {
+                    return None;
+                }
+                Some((pid.trim().parse().ok()?, command_line.to_string()))
+            })
+            .collect())
+    }
+
+    fn terminate(&self, processes: Vec<(u32, String)>) -> Result<Vec<SignalledProcess>, AppError> {
+        if processes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ids = processes
+            .iter()
+            .map(|(pid, _)| pid.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        Command::new("powershell")
+            .args([
+                "-WindowStyle", "Hidden",
+                "-NoProfile",
+                "-Command",
+                &format!("Stop-Process -Force -ErrorAction SilentlyContinue -Id {}", ids),
+            ])
+            .stdin(Stdio::null())
+            .status()?;
+
+        Ok(processes
+            .into_iter()
+            .map(|(pid, command_line)| SignalledProcess {
+                pid,
+                command_line,
+                forced: true,
+            })
+            .collect())
+    }
+}
+
+impl Reloader for NoopReloader {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn find_processes(&self) -> Result<Vec<(u32, String)>, AppError> {
+        Ok(Vec::new())
+    }
+
+    fn terminate(&self, _processes: Vec<(u32, String)>) -> Result<Vec<SignalledProcess>, AppError> {
+        Ok(Vec::new())
+    }
+}
+
+enum Signal {
+    Term,
+    Kill,
+}
+
+#[cfg(unix)]
+fn send_signal(signal: Signal, pids: &[u32]) -> Result<(), AppError> {
+    let signal = match signal {
+        Signal::Term => libc::SIGTERM,
+        Signal::Kill => libc::SIGKILL,
+    };
+    for pid in pids {
+        if unsafe { libc::kill(*pid as libc::pid_t, signal) } != 0 {
+            let err = std::io::Error::last_os_error();
+            // The process exited on its own in the meantime
+            if err.raw_os_error() != Some(libc::ESRCH) {
+                return Err(err.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn send_signal(_signal: Signal, _pids: &[u32]) -> Result<(), AppError> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_alive(pid: u32) -> bool {
+    // Signal 0 only checks that the process exists; EPERM means it does but is not ours
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn is_alive(_pid: u32) -> bool {
+    false
+}
+
+fn terminate_unix(candidates: Vec<(u32, String)>) -> Result<Vec<SignalledProcess>, AppError> {
+    let pids: Vec<u32> = candidates.iter().map(|(pid, _)| *pid).collect();
+    send_signal(Signal::Term, &pids)?;
+
+    let deadline = Instant::now() + TERMINATE_TIMEOUT;
+    let mut survivors = pids;
+    while !survivors.is_empty() && Instant::now() < deadline {
+        sleep(POLL_INTERVAL);
+        survivors.retain(|pid| is_alive(*pid));
+    }
+    send_signal(Signal::Kill, &survivors)?;
+
+    Ok(candidates
+        .into_iter()
+        .map(|(pid, command_line)| SignalledProcess {
+            pid,
+            forced: survivors.contains(&pid),
+            command_line,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(command_line: &str) -> Vec<String> {
+        split_command_line(command_line)
+    }
+
+    #[test]
+    fn matches_only_the_opencode_entrypoint() {
+        assert!(is_opencode_command(&args("/home/me/.opencode/bin/opencode")));
+        assert!(is_opencode_command(&args("node /usr/lib/node_modules/opencode-ai/bin/opencode")));
+        assert!(is_opencode_command(&args(r#""C:\Program Files\nodejs\node.exe" C:\npm\node_modules\opencode-ai\bin\opencode"#)));
+        assert!(is_opencode_command(&args("bun run /home/me/.bun/install/global/node_modules/opencode-ai/bin/opencode")));
+
+        assert!(!is_opencode_command(&args("node /home/me/opencode-github-switcher/node_modules/.bin/vite")));
+        assert!(!is_opencode_command(&args("/usr/bin/opencode-github-switcher")));
+        assert!(!is_opencode_command(&args("node --inspect")));
+    }
+}
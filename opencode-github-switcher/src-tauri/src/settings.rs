@@ -0,0 +1,86 @@
+use crate::auth_config::discover_opencode_dirs;
+use crate::error::AppError;
+use crate::models::TargetDir;
+use sqlx::SqlitePool;
+use std::path::Path;
+
+#[derive(sqlx::FromRow)]
+struct TargetDirRow {
+    path: String,
+    custom: bool,
+    enabled: bool,
+}
+
+/// Discovered OpenCode directories followed by user-added ones, with the persisted enabled flags applied.
+pub async fn list_target_dirs(pool: &SqlitePool) -> Result<Vec<TargetDir>, AppError> {
+    let rows = sqlx::query_as::<_, TargetDirRow>("SELECT path, custom, enabled FROM target_dirs ORDER BY rowid")
+        .fetch_all(pool)
+        .await?;
+
+    let mut targets: Vec<TargetDir> = discover_opencode_dirs()
+        .into_iter()
+        .map(|(dir, source)| {
+            let path = dir.to_string_lossy().into_owned();
+            let enabled = rows
+                .iter()
+                .find(|r| r.path == path)
+                .map(|r| r.enabled)
+                .unwrap_or(true);
+            TargetDir {
+                path,
+                source: source.to_string(),
+                custom: false,
+                enabled,
+            }
+        })
+        .collect();
+
+    for row in rows.into_iter().filter(|r| r.custom) {
+        if !targets.iter().any(|t| t.path == row.path) {
+            targets.push(TargetDir {
+                path: row.path,
+                source: "custom".to_string(),
+                custom: true,
+                enabled: row.enabled,
+            });
+        }
+    }
+    Ok(targets)
+}
+
+pub async fn add_target_dir(pool: &SqlitePool, path: &str) -> Result<(), AppError> {
+    let path = path.trim();
+    if !Path::new(path).is_absolute() {
+        return Err(AppError::System(format!("Target directory must be an absolute path: {}", path)));
+    }
+
+    sqlx::query(
+        "INSERT INTO target_dirs (path, custom, enabled) VALUES (?, 1, 1)
+         ON CONFLICT(path) DO UPDATE SET custom = 1, enabled = 1"
+    )
+    .bind(path)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn remove_target_dir(pool: &SqlitePool, path: &str) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM target_dirs WHERE path = ? AND custom = 1")
+        .bind(path)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn set_target_dir_enabled(pool: &SqlitePool, path: &str, enabled: bool) -> Result<(), AppError> {
+    // Discovered directories only get a row once their flag is changed
+    sqlx::query(
+        "INSERT INTO target_dirs (path, custom, enabled) VALUES (?, 0, ?)
+         ON CONFLICT(path) DO UPDATE SET enabled = excluded.enabled"
+    )
+    .bind(path)
+    .bind(enabled)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
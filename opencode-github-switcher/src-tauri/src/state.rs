@@ -1,15 +1,39 @@
+use crate::database::init_db;
+use crate::error::AppError;
+use crate::vault::VaultKey;
 use sqlx::SqlitePool;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 pub struct AppState {
     pub db: Arc<Mutex<Option<SqlitePool>>>,
+    pub vault_key: Arc<Mutex<Option<VaultKey>>>,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         Self {
             db: Arc::new(Mutex::new(None)),
+            vault_key: Arc::new(Mutex::new(None)),
         }
     }
 }
+
+impl AppState {
+    /// The shared pool, opening the database on first use.
+    pub async fn pool(&self) -> Result<SqlitePool, AppError> {
+        let mut db_guard = self.db.lock().await;
+        if let Some(pool) = db_guard.as_ref() {
+            return Ok(pool.clone());
+        }
+        
+        let pool = init_db().await?;
+        *db_guard = Some(pool.clone());
+        Ok(pool)
+    }
+
+    /// The vault key, if the user has unlocked the vault this session.
+    pub async fn vault_key(&self) -> Option<VaultKey> {
+        self.vault_key.lock().await.clone()
+    }
+}
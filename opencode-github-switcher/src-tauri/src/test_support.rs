@@ -0,0 +1,83 @@
+//! A tiny HTTP server on localhost for exercising the network code without real services.
+
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+}
+
+/// Starts a server that answers every request with `respond(method, path)` as a JSON
+/// `(status, body)` and records what it received. Returns the base URL.
+pub async fn spawn_server(
+    respond: fn(&str, &str) -> (u16, String),
+) -> (String, Arc<Mutex<Vec<RecordedRequest>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let recorded = requests.clone();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(_) => return,
+            };
+            let mut raw = Vec::new();
+            let mut buf = [0u8; 4096];
+            // Read the head, then as much body as Content-Length announces
+            let head_end = loop {
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                if n == 0 {
+                    break None;
+                }
+                raw.extend_from_slice(&buf[..n]);
+                if let Some(pos) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break Some(pos + 4);
+                }
+            };
+            let head_end = match head_end {
+                Some(end) => end,
+                None => continue,
+            };
+            let head = String::from_utf8_lossy(&raw[..head_end]).into_owned();
+            let content_length = head
+                .lines()
+                .find_map(|l| {
+                    let (name, value) = l.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length").then(|| value.trim().parse::<usize>().ok())?
+                })
+                .unwrap_or(0);
+            while raw.len() < head_end + content_length {
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                if n == 0 {
+                    break;
+                }
+                raw.extend_from_slice(&buf[..n]);
+            }
+
+            let mut request_line = head.lines().next().unwrap_or("").split_whitespace();
+            let method = request_line.next().unwrap_or("").to_string();
+            let path = request_line.next().unwrap_or("").to_string();
+            let (status, body) = respond(&method, &path);
+            recorded.lock().unwrap().push(RecordedRequest {
+                method,
+                path,
+                body: String::from_utf8_lossy(&raw[head_end..]).into_owned(),
+            });
+
+            let response = format!(
+                "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    });
+
+    (url, requests)
+}
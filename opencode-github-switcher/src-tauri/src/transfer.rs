@@ -0,0 +1,337 @@
+use crate::auth_config::write_file_atomic;
+use crate::error::AppError;
+use crate::migrations::run_migrations;
+use crate::models::{GitHubProvider, ImportReport};
+use crate::provider_service::{list_providers, normalize_tags};
+use crate::vault::{self, ensure_unlocked, open_token, seal_token, seal_with_passphrase, unseal_with_passphrase, VaultKey};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{SqliteConnection, SqlitePool};
+use std::fs;
+use std::path::Path;
+use uuid::Uuid;
+
+const EXPORT_FORMAT: &str = "opencode-github-switcher-accounts";
+const EXPORT_VERSION: u32 = 1;
+
+/// On-disk envelope; `payload` is the sealed JSON list of accounts.
+#[derive(Serialize, Deserialize)]
+struct ExportFile {
+    format: String,
+    version: u32,
+    salt: String,
+    payload: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportedAccount {
+    host: String,
+    github_id: i64,
+    name: String,
+    access_token: String,
+    email: Option<String>,
+    avatar_url: Option<String>,
+    created_at: i64,
+    last_used_at: Option<i64>,
+    display_name: Option<String>,
+    notes: Option<String>,
+    color: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl ExportedAccount {
+    fn from_provider(p: GitHubProvider, key: Option<&VaultKey>) -> Result<Self, AppError> {
+        Ok(ExportedAccount {
+            access_token: open_token(key, &p.access_token)?,
+            host: p.host,
+            github_id: p.github_id,
+            name: p.name,
+            email: p.email,
+            avatar_url: p.avatar_url,
+            created_at: p.created_at,
+            last_used_at: p.last_used_at,
+            display_name: p.display_name,
+            notes: p.notes,
+            color: p.color,
+            tags: p.tags,
+        })
+    }
+
+    fn recency(&self) -> i64 {
+        self.last_used_at.unwrap_or(self.created_at)
+    }
+}
+
+/// Writes every account, tokens included, to `path` encrypted with `passphrase`.
+/// Returns how many accounts were exported.
+pub async fn export_accounts(
+    pool: &SqlitePool,
+    key: Option<&VaultKey>,
+    path: &Path,
+    passphrase: &str,
+) -> Result<usize, AppError> {
+    require_absolute(path)?;
+    let accounts = list_providers(pool, None)
+        .await?
+        .into_iter()
+        .map(|p| ExportedAccount::from_provider(p, key))
+        .collect::<Result<Vec<_>, AppError>>()?;
+
+    let (salt, payload) = seal_with_passphrase(passphrase, &serde_json::to_string(&accounts)?).await?;
+    let file = ExportFile {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_VERSION,
+        salt,
+        payload,
+    };
+    write_file_atomic(path, &serde_json::to_string_pretty(&file)?)?;
+    Ok(accounts.len())
+}
+
+fn require_absolute(path: &Path) -> Result<(), AppError> {
+    if !path.is_absolute() {
+        return Err(AppError::InvalidInput(format!("Path must be absolute: {}", path.display())));
+    }
+    Ok(())
+}
+
+async fn read_export_file(path: &Path, passphrase: &str) -> Result<Vec<ExportedAccount>, AppError> {
+    let file: ExportFile = serde_json::from_str(&fs::read_to_string(path)?)
+        .map_err(|e| AppError::InvalidInput(format!("{} is not an account export: {}", path.display(), e)))?;
+    if file.format != EXPORT_FORMAT {
+        return Err(AppError::InvalidInput(format!("{} is not an account export", path.display())));
+    }
+    if file.version > EXPORT_VERSION {
+        return Err(AppError::InvalidInput(format!(
+            "{} was written by a newer version of the app (format {})",
+            path.display(),
+            file.version
+        )));
+    }
+    let payload = unseal_with_passphrase(passphrase, &file.salt, &file.payload).await?;
+    Ok(serde_json::from_str(&payload)?)
+}
+
+async fn insert_tags(conn: &mut SqliteConnection, provider_id: &str, tags: &[String]) -> Result<(), AppError> {
+    for tag in tags {
+        sqlx::query("INSERT OR IGNORE INTO provider_tags (provider_id, tag) VALUES (?, ?)")
+            .bind(provider_id)
+            .bind(tag)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Merges the export at `path`, sealed with `passphrase`, into the local accounts.
+pub async fn import_accounts(
+    pool: &SqlitePool,
+    key: Option<&VaultKey>,
+    path: &Path,
+    passphrase: &str,
+) -> Result<ImportReport, AppError> {
+    require_absolute(path)?;
+    // Fail up front rather than halfway through the merge
+    ensure_unlocked(pool, key).await?;
+    let accounts = read_export_file(path, passphrase).await?;
+    merge_accounts(pool, key, accounts).await
+}
+
+/// Reads the accounts out of another install's providers.db. The file is opened read-only,
+/// copied into memory and the copy migrated to the current schema, so databases from any
+/// older version work and nothing is written to disk. `passphrase` unlocks that database's
+/// own vault, if it has one.
+async fn read_foreign_database(path: &Path, passphrase: Option<&str>) -> Result<Vec<ExportedAccount>, AppError> {
+    if !path.is_file() {
+        return Err(AppError::InvalidInput(format!("{} does not exist", path.display())));
+    }
+    // A single connection that is never recycled, since each one has its own memory database
+    let source = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect_with(SqliteConnectOptions::new().in_memory(true))
+        .await?;
+    let accounts = async {
+        copy_into_memory(&mut *source.acquire().await?, path).await?;
+        run_migrations(&source, None).await?;
+        let key = if vault::is_initialized(&source).await? {
+            let passphrase = passphrase.ok_or_else(|| {
+                AppError::InvalidInput("The tokens in that database are encrypted; enter its passphrase".to_string())
+            })?;
+            Some(vault::unlock(&source, passphrase).await?)
+        } else {
+            None
+        };
+        list_providers(&source, None)
+            .await?
+            .into_iter()
+            .map(|p| ExportedAccount::from_provider(p, key.as_ref()))
+            .collect::<Result<Vec<_>, AppError>>()
+    }
+    .await;
+    source.close().await;
+    accounts
+}
+
+/// Recreates every table, index and trigger of the database at `path` in the connection's
+/// main database, rows included.
+async fn copy_into_memory(conn: &mut SqliteConnection, path: &Path) -> Result<(), AppError> {
+    sqlx::query("ATTACH DATABASE ? AS source")
+        .bind(read_only_uri(path))
+        .execute(&mut *conn)
+        .await?;
+    let result = async {
+        // Tables first so indexes and triggers have something to attach to
+        let objects: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT type, name, sql FROM source.sqlite_master
+             WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%'
+             ORDER BY type <> 'table'",
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        for (kind, name, sql) in objects {
+            sqlx::query(&sql).execute(&mut *conn).await?;
+            if kind == "table" {
+                let name = name.replace('"', "\"\"");
+                sqlx::query(&format!("INSERT INTO main.\"{}\" SELECT * FROM source.\"{}\"", name, name))
+                    .execute(&mut *conn)
+                    .await?;
+            }
+        }
+        Ok::<_, AppError>(())
+    }
+    .await;
+    sqlx::query("DETACH DATABASE source").execute(&mut *conn).await?;
+    result
+}
+
+/// SQLite URI that opens `path` read-only, which also keeps SQLite from checkpointing its
+/// write-ahead log into it.
+fn read_only_uri(path: &Path) -> String {
+    let mut path = path.to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        path.insert(0, '/');
+    }
+    let path = path.replace('%', "%25").replace('?', "%3f").replace('#', "%23");
+    format!("file://{}?mode=ro", path)
+}
+
+pub async fn merge_database(
+    pool: &SqlitePool,
+    key: Option<&VaultKey>,
+    path: &Path,
+    source_passphrase: Option<&str>,
+) -> Result<ImportReport, AppError> {
+    require_absolute(path)?;
+    ensure_unlocked(pool, key).await?;
+    let accounts = read_foreign_database(path, source_passphrase).await?;
+    merge_accounts(pool, key, accounts).await
+}
+
+/// Merges accounts into the local ones by GitHub user, all or nothing. An existing account
+/// takes the incoming token only if the incoming one was used more recently, fills in labels
+/// it does not have yet and gains the incoming tags; otherwise it is reported as skipped.
+async fn merge_accounts(
+    pool: &SqlitePool,
+    key: Option<&VaultKey>,
+    accounts: Vec<ExportedAccount>,
+) -> Result<ImportReport, AppError> {
+    let mut report = ImportReport::default();
+    // Sealed up front so the transaction below only uses its own connection
+    let mut sealed = Vec::with_capacity(accounts.len());
+    for account in &accounts {
+        sealed.push(seal_token(pool, key, &account.access_token).await?);
+    }
+
+    let mut tx = pool.begin().await?;
+    for (account, sealed_token) in accounts.into_iter().zip(sealed) {
+        let tags = normalize_tags(&account.tags);
+        let existing = sqlx::query_as::<_, GitHubProvider>("SELECT * FROM github_providers WHERE host = ? AND github_id = ?")
+            .bind(&account.host)
+            .bind(account.github_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let existing = match existing {
+            Some(existing) => existing,
+            None => {
+                let id = Uuid::new_v4().to_string();
+                sqlx::query(
+                    "INSERT INTO github_providers (id, host, name, access_token, email, avatar_url, github_id, created_at, last_used_at, display_name, notes, color)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+                )
+                .bind(&id)
+                .bind(&account.host)
+                .bind(&account.name)
+                .bind(&sealed_token)
+                .bind(&account.email)
+                .bind(&account.avatar_url)
+                .bind(account.github_id)
+                .bind(account.created_at)
+                .bind(account.last_used_at)
+                .bind(&account.display_name)
+                .bind(&account.notes)
+                .bind(&account.color)
+                .execute(&mut *tx)
+                .await?;
+                insert_tags(&mut tx, &id, &tags).await?;
+                report.added.push(account.name);
+                continue;
+            }
+        };
+
+        let local_token = open_token(key, &existing.access_token)?;
+        let local_recency = existing.last_used_at.unwrap_or(existing.created_at);
+        let take_token = local_token != account.access_token && account.recency() > local_recency;
+        let fill_labels = (existing.display_name.is_none() && account.display_name.is_some())
+            || (existing.notes.is_none() && account.notes.is_some())
+            || (existing.color.is_none() && account.color.is_some());
+        let current_tags: Vec<String> = sqlx::query_scalar("SELECT tag FROM provider_tags WHERE provider_id = ?")
+            .bind(&existing.id)
+            .fetch_all(&mut *tx)
+            .await?;
+        let new_tags: Vec<String> = tags.into_iter().filter(|t| !current_tags.contains(t)).collect();
+
+        if !take_token && !fill_labels && new_tags.is_empty() {
+            report.skipped.push(account.name);
+            continue;
+        }
+
+        if take_token {
+            // The recorded health belonged to the token being replaced
+            sqlx::query(
+                "UPDATE github_providers SET access_token = ?, name = ?, email = ?, avatar_url = ?,
+                     last_validated_at = NULL, token_status = NULL, last_http_status = NULL
+                 WHERE id = ?"
+            )
+            .bind(&sealed_token)
+            .bind(&account.name)
+            .bind(&account.email)
+            .bind(&account.avatar_url)
+            .bind(&existing.id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query(
+            "UPDATE github_providers SET
+                 display_name = COALESCE(display_name, ?),
+                 notes = COALESCE(notes, ?),
+                 color = COALESCE(color, ?),
+                 created_at = MIN(created_at, ?)
+             WHERE id = ?"
+        )
+        .bind(&account.display_name)
+        .bind(&account.notes)
+        .bind(&account.color)
+        .bind(account.created_at)
+        .bind(&existing.id)
+        .execute(&mut *tx)
+        .await?;
+        insert_tags(&mut tx, &existing.id, &new_tags).await?;
+        report.updated.push(account.name);
+    }
+    tx.commit().await?;
+    Ok(report)
+}
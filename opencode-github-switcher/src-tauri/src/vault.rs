@@ -0,0 +1,237 @@
+use crate::auth_config::run_blocking;
+use crate::error::AppError;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use sqlx::{Sqlite, SqlitePool, Transaction};
+use zeroize::{Zeroize, Zeroizing};
+
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+// Marks a stored token as ciphertext; anything else is a legacy plaintext token
+const SEALED_PREFIX: &str = "vault:v1:";
+// Encrypted with the key on setup so a wrong passphrase can be told apart from a right one
+const VERIFIER_PLAINTEXT: &str = "opencode-github-switcher";
+
+/// Key derived from the master passphrase. Only ever kept in memory, wiped on drop.
+#[derive(Clone)]
+pub struct VaultKey([u8; KEY_LEN]);
+
+impl Drop for VaultKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+// Argon2 is slow on purpose, so it runs on the blocking pool rather than an async worker
+async fn derive_key(passphrase: &str, salt: &[u8]) -> Result<VaultKey, AppError> {
+    if passphrase.is_empty() {
+        return Err(AppError::Vault("Passphrase must not be empty".to_string()));
+    }
+    let passphrase = Zeroizing::new(passphrase.to_string());
+    let salt = salt.to_vec();
+    run_blocking(move || {
+        let mut key = [0u8; KEY_LEN];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| AppError::Vault(e.to_string()))?;
+        Ok(VaultKey(key))
+    })
+    .await
+}
+
+fn new_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+pub fn is_sealed(stored: &str) -> bool {
+    stored.starts_with(SEALED_PREFIX)
+}
+
+fn seal(key: &VaultKey, plaintext: &str) -> Result<String, AppError> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key.0));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| AppError::Vault("Failed to encrypt token".to_string()))?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", SEALED_PREFIX, BASE64.encode(payload)))
+}
+
+fn unseal(key: &VaultKey, sealed: &str) -> Result<String, AppError> {
+    let payload = sealed
+        .strip_prefix(SEALED_PREFIX)
+        .and_then(|p| BASE64.decode(p).ok())
+        .filter(|p| p.len() > NONCE_LEN)
+        .ok_or_else(|| AppError::Vault("Malformed encrypted token".to_string()))?;
+    let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key.0));
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| AppError::InvalidPassphrase)?;
+    String::from_utf8(plaintext).map_err(|_| AppError::Vault("Malformed encrypted token".to_string()))
+}
+
+async fn load_meta(pool: &SqlitePool) -> Result<Option<(String, String)>, AppError> {
+    let meta = sqlx::query_as::<_, (String, String)>("SELECT salt, verifier FROM vault WHERE id = 1")
+        .fetch_optional(pool)
+        .await?;
+    Ok(meta)
+}
+
+pub async fn is_initialized(pool: &SqlitePool) -> Result<bool, AppError> {
+    Ok(load_meta(pool).await?.is_some())
+}
+
+/// Re-encrypts every stored token with `new`, reading sealed ones with `old`.
+async fn reseal_tokens(
+    tx: &mut Transaction<'_, Sqlite>,
+    old: Option<&VaultKey>,
+    new: &VaultKey,
+) -> Result<(), AppError> {
+    let rows = sqlx::query_as::<_, (String, String)>("SELECT id, access_token FROM github_providers")
+        .fetch_all(&mut **tx)
+        .await?;
+    for (id, stored) in rows {
+        let token = open_token(old, &stored)?;
+        sqlx::query("UPDATE github_providers SET access_token = ? WHERE id = ?")
+            .bind(seal(new, &token)?)
+            .bind(&id)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Creates the vault and encrypts every existing plaintext token with the new passphrase.
+pub async fn setup(pool: &SqlitePool, passphrase: &str) -> Result<VaultKey, AppError> {
+    if is_initialized(pool).await? {
+        return Err(AppError::Vault("The token vault is already set up".to_string()));
+    }
+    let salt = new_salt();
+    let key = derive_key(passphrase, &salt).await?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("INSERT INTO vault (id, salt, verifier) VALUES (1, ?, ?)")
+        .bind(BASE64.encode(salt))
+        .bind(seal(&key, VERIFIER_PLAINTEXT)?)
+        .execute(&mut *tx)
+        .await?;
+    reseal_tokens(&mut tx, None, &key).await?;
+    tx.commit().await?;
+
+    // Rewrite the file so the replaced plaintext tokens do not linger in free pages
+    sqlx::query("VACUUM").execute(pool).await?;
+    Ok(key)
+}
+
+/// Derives the key from `passphrase` and checks it against the stored verifier.
+pub async fn unlock(pool: &SqlitePool, passphrase: &str) -> Result<VaultKey, AppError> {
+    let (salt, verifier) = load_meta(pool)
+        .await?
+        .ok_or_else(|| AppError::Vault("The token vault is not set up".to_string()))?;
+    let salt = BASE64
+        .decode(salt)
+        .map_err(|_| AppError::Vault("Malformed vault salt".to_string()))?;
+    let key = derive_key(passphrase, &salt).await?;
+    if unseal(&key, &verifier)? != VERIFIER_PLAINTEXT {
+        return Err(AppError::InvalidPassphrase);
+    }
+
+    // Encrypt plaintext tokens left behind by a build that predates the vault
+    let mut tx = pool.begin().await?;
+    let plaintext = sqlx::query_as::<_, (String, String)>("SELECT id, access_token FROM github_providers")
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .filter(|(_, stored)| !is_sealed(stored));
+    for (id, token) in plaintext {
+        sqlx::query("UPDATE github_providers SET access_token = ? WHERE id = ?")
+            .bind(seal(&key, &token)?)
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(key)
+}
+
+/// Re-encrypts every token under a key derived from `new_passphrase` with a fresh salt.
+pub async fn change_passphrase(pool: &SqlitePool, current_passphrase: &str, new_passphrase: &str) -> Result<VaultKey, AppError> {
+    let old_key = unlock(pool, current_passphrase).await?;
+    let salt = new_salt();
+    let new_key = derive_key(new_passphrase, &salt).await?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("UPDATE vault SET salt = ?, verifier = ? WHERE id = 1")
+        .bind(BASE64.encode(salt))
+        .bind(seal(&new_key, VERIFIER_PLAINTEXT)?)
+        .execute(&mut *tx)
+        .await?;
+    reseal_tokens(&mut tx, Some(&old_key), &new_key).await?;
+    tx.commit().await?;
+    Ok(new_key)
+}
+
+/// Last four characters and a short SHA-256 prefix of a secret, enough to tell tokens apart
+/// without revealing them.
+pub fn fingerprint(secret: &str) -> (String, String) {
+    let chars: Vec<char> = secret.chars().collect();
+    let last4 = chars[chars.len().saturating_sub(4)..].iter().collect();
+    let hash = Sha256::digest(secret.as_bytes())
+        .iter()
+        .take(4)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    (last4, hash)
+}
+
+/// The usable token for a stored value, which may still be legacy plaintext.
+pub fn open_token(key: Option<&VaultKey>, stored: &str) -> Result<String, AppError> {
+    if !is_sealed(stored) {
+        return Ok(stored.to_string());
+    }
+    unseal(key.ok_or(AppError::VaultLocked)?, stored)
+}
+
+/// Encrypts `plaintext` under a key derived from `passphrase` and a fresh salt, e.g. for an
+/// export file. Returns the base64 salt and the sealed text.
+pub async fn seal_with_passphrase(passphrase: &str, plaintext: &str) -> Result<(String, String), AppError> {
+    let salt = new_salt();
+    let key = derive_key(passphrase, &salt).await?;
+    Ok((BASE64.encode(salt), seal(&key, plaintext)?))
+}
+
+pub async fn unseal_with_passphrase(passphrase: &str, salt: &str, sealed: &str) -> Result<String, AppError> {
+    let salt = BASE64
+        .decode(salt)
+        .map_err(|_| AppError::Vault("Malformed salt".to_string()))?;
+    unseal(&derive_key(passphrase, &salt).await?, sealed)
+}
+
+/// Fails with `VaultLocked` if tokens are encrypted but no key is available.
+pub async fn ensure_unlocked(pool: &SqlitePool, key: Option<&VaultKey>) -> Result<(), AppError> {
+    if key.is_none() && is_initialized(pool).await? {
+        return Err(AppError::VaultLocked);
+    }
+    Ok(())
+}
+
+/// The value to store for `token`: encrypted once the vault exists, plaintext before that.
+pub async fn seal_token(pool: &SqlitePool, key: Option<&VaultKey>, token: &str) -> Result<String, AppError> {
+    ensure_unlocked(pool, key).await?;
+    match key {
+        Some(key) => seal(key, token),
+        None => Ok(token.to_string()),
+    }
+}
@@ -0,0 +1,72 @@
+use crate::auth_config::is_own_write;
+use crate::models::TargetDir;
+use crate::provider_service::{summarize, sync_active_account};
+use crate::settings::list_target_dirs;
+use crate::state::AppState;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter, Manager};
+
+pub const ACTIVE_ACCOUNT_CHANGED_EVENT: &str = "active-account-changed";
+
+// Polling is used on purpose instead of a native file watcher: it copes with directories
+// that do not exist yet, with custom directories added at runtime and with files replaced
+// by rename, all of which a native watcher needs re-registration for
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+type Fingerprint = Vec<(String, Option<(SystemTime, u64)>)>;
+
+fn fingerprint(targets: &[TargetDir]) -> Fingerprint {
+    targets
+        .iter()
+        .map(|target| {
+            let path = Path::new(&target.path).join("auth.json");
+            let stamp = fs::metadata(&path)
+                .and_then(|m| Ok((m.modified()?, m.len())))
+                .ok();
+            (path.to_string_lossy().into_owned(), stamp)
+        })
+        .collect()
+}
+
+/// Re-syncs the active account whenever an auth.json changes on disk, e.g. after
+/// `opencode auth login`, and tells the UI about the result.
+pub async fn watch_auth_files(app: AppHandle) {
+    let mut last: Option<Fingerprint> = None;
+    let mut ticker = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        ticker.tick().await;
+
+        let state = app.state::<AppState>();
+        let pool = match state.pool().await {
+            Ok(pool) => pool,
+            Err(_) => continue,
+        };
+        let targets = match list_target_dirs(&pool).await {
+            Ok(targets) => targets,
+            Err(_) => continue,
+        };
+
+        let current = fingerprint(&targets);
+        let changed: Vec<String> = match &last {
+            Some(previous) => current
+                .iter()
+                .filter(|entry| !previous.contains(entry))
+                .map(|(path, _)| path.clone())
+                .collect(),
+            None => Vec::new(),
+        };
+        last = Some(current);
+        // A switch made in the app already updated everything; only react to other writers
+        if changed.iter().all(|path| is_own_write(Path::new(path))) {
+            continue;
+        }
+
+        let key = state.vault_key().await;
+        if let Ok(Some(provider)) = sync_active_account(&pool, key.as_ref()).await {
+            let _ = app.emit(ACTIVE_ACCOUNT_CHANGED_EVENT, summarize(&provider, key.as_ref()));
+        }
+    }
+}
@@ -1,11 +1,17 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 
-import { Github, Plus, Trash2, CheckCircle2, Copy, ExternalLink, Loader2, RefreshCw, Globe } from 'lucide-react';
+import { Github, Plus, Trash2, CheckCircle2, Copy, ExternalLink, Loader2, RefreshCw, Globe, Settings, FolderOpen, LogOut, Lock, Pencil, History, Undo2, KeyRound, RotateCcw } from 'lucide-react';
 import { formatDistanceToNow } from 'date-fns';
 import { enUS, zhCN, ja } from 'date-fns/locale';
 import { translations, languages } from './i18n';
 
+// Commands reject with { kind, message }; anything else is a plain JS error or string
+const errorMessage = (e) => e?.message ?? String(e);
+// Only a failed token check can be skipped by forcing the switch
+const canForceSwitch = (e) => e?.kind === 'TokenRejected' || e?.kind === 'TokenUnverified';
+
 export default function App() {
   const [providers, setProviders] = useState([]);
   const [loading, setLoading] = useState(true);
@@ -16,6 +22,24 @@ export default function App() {
   const [copied, setCopied] = useState(false);
   const [toast, setToast] = useState(null);
   const [isSyncing, setIsSyncing] = useState(false);
+  const [showTargets, setShowTargets] = useState(false);
+  const [targetDirs, setTargetDirs] = useState([]);
+  const [newTargetDir, setNewTargetDir] = useState('');
+  const [normalizePreview, setNormalizePreview] = useState(null);
+  const [switchOutcome, setSwitchOutcome] = useState(null);
+  const [authFiles, setAuthFiles] = useState(null);
+  const [vaultStatus, setVaultStatus] = useState(null);
+  const [passphrase, setPassphrase] = useState('');
+  const [newPassphrase, setNewPassphrase] = useState('');
+  const [tagFilter, setTagFilter] = useState('');
+  const [filteredIds, setFilteredIds] = useState(null);
+  const [editingMetadata, setEditingMetadata] = useState(null);
+  const [activeId, setActiveId] = useState(null);
+  const [history, setHistory] = useState(null);
+  const [transferPath, setTransferPath] = useState('');
+  const [transferPassphrase, setTransferPassphrase] = useState('');
+  const [snapshots, setSnapshots] = useState(null);
+  const [snapshotDiff, setSnapshotDiff] = useState(null);
 
   
   const [currentLang, setCurrentLang] = useState(() => {
@@ -45,9 +69,10 @@ export default function App() {
       setLoading(true);
       const data = await invoke('list_providers');
       setProviders(data);
+      setActiveId(await invoke('get_active_provider_id'));
       setError(null);
     } catch (e) {
-      setError(e.toString());
+      setError(errorMessage(e));
     } finally {
       setLoading(false);
     }
@@ -65,16 +90,78 @@ export default function App() {
     }
   };
 
+  const fetchVaultStatus = async () => {
+    try {
+      setVaultStatus(await invoke('vault_status'));
+    } catch (e) {
+      setError(t('failVault') + errorMessage(e));
+    }
+  };
+
+  const handleVaultAction = async (command, args) => {
+    try {
+      await invoke(command, args);
+      setPassphrase('');
+      setNewPassphrase('');
+      await fetchVaultStatus();
+      return true;
+    } catch (e) {
+      setError(t('failVault') + errorMessage(e));
+      return false;
+    }
+  };
+
+  const handleUnlockVault = async () => {
+    if (await handleVaultAction('unlock_vault', { passphrase })) {
+      await syncActiveAccount();
+    }
+  };
+
+  const handleSetupVault = async () => {
+    if (!newPassphrase) return;
+    if (await handleVaultAction('setup_vault', { passphrase: newPassphrase })) {
+      setToast(t('vaultEnabled'));
+      setTimeout(() => setToast(null), 3000);
+    }
+  };
+
+  const handleChangePassphrase = async () => {
+    if (!passphrase || !newPassphrase) return;
+    if (await handleVaultAction('change_vault_passphrase', { currentPassphrase: passphrase, newPassphrase })) {
+      setToast(t('passphraseChanged'));
+      setTimeout(() => setToast(null), 3000);
+    }
+  };
+
   useEffect(() => {
     const init = async () => {
+      await fetchVaultStatus();
       await syncActiveAccount();
       // fetchProviders is already called inside syncActiveAccount, but we can call it again or rely on it.
       await fetchProviders();
     };
     init();
+
+    // The backend re-syncs when OpenCode's auth.json changes outside the app
+    const unlisten = listen('active-account-changed', () => {
+      fetchProviders();
+    });
+    // The backend periodically checks every stored token against GitHub
+    const unlistenTokenStatus = listen('token-status-changed', (event) => {
+      fetchProviders();
+      if (event.payload.status === 'revoked') {
+        setToast(t('tokenRevokedNotice').replace('{name}', event.payload.name));
+        setTimeout(() => setToast(null), 5000);
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+      unlistenTokenStatus.then((fn) => fn());
+    };
   }, []);
 
-  const handleStartAuth = async () => {
+  // With an id, the new token replaces that account's token instead of adding an account
+  const handleStartAuth = async (reauthorizeId = null) => {
     try {
       setIsAuthenticating(true);
       setError(null);
@@ -99,19 +186,26 @@ export default function App() {
         console.error("Failed to auto-open browser:", err);
       }
       
-      const provider = await invoke('complete_device_flow', {
+      const flow = {
         clientId: CLIENT_ID,
         deviceCode: response.device_code,
         interval: response.interval,
         expiresIn: response.expires_in
-      });
+      };
+      if (reauthorizeId) {
+        await invoke('reauthorize_provider', { id: reauthorizeId, ...flow });
+        setToast(t('reauthorized'));
+        setTimeout(() => setToast(null), 3000);
+      } else {
+        await invoke('complete_device_flow', flow);
+      }
       
       setDeviceCode(null);
       setIsAuthenticating(false);
       await fetchProviders();
       
     } catch (e) {
-      setError(e.toString());
+      setError(errorMessage(e));
       setDeviceCode(null);
       setIsAuthenticating(false);
     }
@@ -131,12 +225,189 @@ export default function App() {
     }
   };
 
-  const handleSwitch = async (id) => {
+  // The backend refuses tokens GitHub rejects or cannot verify; offer to skip the check, e.g. offline
+  const handleSwitch = async (id, force = false) => {
+    try {
+      const outcome = await invoke('switch_provider', { id, force });
+      setSwitchOutcome(outcome);
+      await fetchProviders();
+    } catch (e) {
+      await fetchProviders();
+      if (!force && canForceSwitch(e) && confirm(t('failSwitch') + errorMessage(e) + '\n\n' + t('confirmForceSwitch'))) {
+        return handleSwitch(id, true);
+      }
+      setError(t('failSwitch') + errorMessage(e));
+    }
+  };
+
+  const handleSwitchBack = async (force = false) => {
+    try {
+      setSwitchOutcome(await invoke('switch_back', { force }));
+      await fetchProviders();
+      if (history) setHistory(await invoke('list_switch_history'));
+    } catch (e) {
+      await fetchProviders();
+      if (!force && canForceSwitch(e) && confirm(t('failSwitch') + errorMessage(e) + '\n\n' + t('confirmForceSwitch'))) {
+        return handleSwitchBack(true);
+      }
+      setError(t('failSwitch') + errorMessage(e));
+    }
+  };
+
+  const handleToggleHistory = async () => {
+    if (history) {
+      setHistory(null);
+      return;
+    }
+    try {
+      setHistory(await invoke('list_switch_history'));
+    } catch (e) {
+      setError(t('failHistory') + errorMessage(e));
+    }
+  };
+
+  const fetchTargetDirs = async () => {
+    try {
+      setTargetDirs(await invoke('list_target_dirs'));
+    } catch (e) {
+      setError(t('failTargetDir') + errorMessage(e));
+    }
+  };
+
+  const handleToggleTargets = async () => {
+    if (!showTargets) {
+      await fetchTargetDirs();
+    }
+    setShowTargets(!showTargets);
+  };
+
+  const handleTargetDirAction = async (command, args) => {
+    try {
+      setTargetDirs(await invoke(command, args));
+    } catch (e) {
+      setError(t('failTargetDir') + errorMessage(e));
+    }
+  };
+
+  const handleAddTargetDir = async () => {
+    if (!newTargetDir.trim()) return;
+    await handleTargetDirAction('add_target_dir', { path: newTargetDir.trim() });
+    setNewTargetDir('');
+  };
+
+  const handlePreviewNormalize = async () => {
+    try {
+      const previews = await invoke('preview_normalize_auth');
+      if (previews.length === 0) {
+        setNormalizePreview(null);
+        setToast(t('nothingToNormalize'));
+        setTimeout(() => setToast(null), 3000);
+      } else {
+        setNormalizePreview(previews);
+      }
+    } catch (e) {
+      setError(t('failNormalize') + errorMessage(e));
+    }
+  };
+
+  const handleApplyNormalize = async () => {
+    try {
+      await invoke('normalize_auth', { paths: normalizePreview.filter(p => !p.error).map(p => p.path) });
+      setNormalizePreview(null);
+      setToast(t('normalizeDone'));
+      setTimeout(() => setToast(null), 3000);
+    } catch (e) {
+      setError(t('failNormalize') + errorMessage(e));
+    }
+  };
+
+  const handleExportAccounts = async () => {
+    if (!transferPath.trim() || !transferPassphrase) return;
+    try {
+      const count = await invoke('export_accounts', { path: transferPath, passphrase: transferPassphrase });
+      setTransferPassphrase('');
+      setToast(t('exportDone').replace('{count}', count));
+      setTimeout(() => setToast(null), 3000);
+    } catch (e) {
+      setError(t('failTransfer') + errorMessage(e));
+    }
+  };
+
+  const handleImportAccounts = async (fromDatabase) => {
+    if (!transferPath.trim() || (!fromDatabase && !transferPassphrase)) return;
+    try {
+      const report = fromDatabase
+        ? await invoke('merge_database', { path: transferPath, sourcePassphrase: transferPassphrase })
+        : await invoke('import_accounts', { path: transferPath, passphrase: transferPassphrase });
+      setTransferPassphrase('');
+      await fetchProviders();
+      setToast(t('importDone')
+        .replace('{added}', report.added.length)
+        .replace('{updated}', report.updated.length)
+        .replace('{skipped}', report.skipped.length));
+      setTimeout(() => setToast(null), 5000);
+    } catch (e) {
+      setError(t('failTransfer') + errorMessage(e));
+    }
+  };
+
+  const handleInspectAuthFiles = async () => {
+    try {
+      setAuthFiles(await invoke('inspect_auth_files'));
+    } catch (e) {
+      setError(t('failInspect') + errorMessage(e));
+    }
+  };
+
+  const handleToggleSnapshots = async () => {
+    if (snapshots) {
+      setSnapshots(null);
+      setSnapshotDiff(null);
+      return;
+    }
+    try {
+      setSnapshots(await invoke('list_auth_snapshots'));
+    } catch (e) {
+      setError(t('failSnapshots') + errorMessage(e));
+    }
+  };
+
+  const handleDiffSnapshot = async (snapshot) => {
+    if (snapshotDiff && snapshotDiff.id === snapshot.id && snapshotDiff.target_path === snapshot.target_path) {
+      setSnapshotDiff(null);
+      return;
+    }
+    try {
+      const diff = await invoke('diff_auth_snapshot', { targetPath: snapshot.target_path, id: snapshot.id });
+      setSnapshotDiff({ ...snapshot, diff });
+    } catch (e) {
+      setError(t('failSnapshots') + errorMessage(e));
+    }
+  };
+
+  const handleRestoreSnapshot = async (snapshot) => {
+    if (!confirm(t('confirmRestoreSnapshot').replace('{path}', snapshot.target_path))) return;
+    try {
+      await invoke('restore_auth_snapshot', { targetPath: snapshot.target_path, id: snapshot.id });
+      setSnapshotDiff(null);
+      // Restoring snapshots the replaced file, so the list has grown
+      setSnapshots(await invoke('list_auth_snapshots'));
+      await syncActiveAccount();
+      setToast(t('snapshotRestored'));
+      setTimeout(() => setToast(null), 3000);
+    } catch (e) {
+      setError(t('failSnapshots') + errorMessage(e));
+    }
+  };
+
+  const handleAlignAuthFiles = async (id) => {
     try {
-      await invoke('switch_provider', { id });
+      const outcome = await invoke('align_auth_files', { id });
+      setSwitchOutcome(outcome);
       await fetchProviders();
+      setAuthFiles(await invoke('inspect_auth_files'));
     } catch (e) {
-      setError(t('failSwitch') + e.toString());
+      setError(t('failSwitch') + errorMessage(e));
     }
   };
 
@@ -144,20 +415,68 @@ export default function App() {
     if (!confirm(t('confirmRemove'))) return;
     
     try {
-      await invoke('delete_provider', { id });
+      const wasActive = await invoke('delete_provider', { id });
       await fetchProviders();
+      if (wasActive && confirm(t('confirmSignOutAfterDelete'))) {
+        await handleSignOut();
+      }
+    } catch (e) {
+      setError(t('failDelete') + errorMessage(e));
+    }
+  };
+
+  const handleSignOut = async () => {
+    try {
+      setSwitchOutcome(await invoke('sign_out'));
     } catch (e) {
-      setError(t('failDelete') + e.toString());
+      setError(t('failSignOut') + errorMessage(e));
     }
   };
 
+  useEffect(() => {
+    if (!tagFilter) {
+      setFilteredIds(null);
+      return;
+    }
+    invoke('list_providers', { tag: tagFilter })
+      .then((list) => setFilteredIds(list.map((p) => p.id)))
+      .catch((e) => setError(errorMessage(e)));
+  }, [tagFilter, providers]);
+
+  const handleEditMetadata = (provider) => {
+    setEditingMetadata({
+      id: provider.id,
+      display_name: provider.display_name || '',
+      notes: provider.notes || '',
+      color: provider.color || '',
+      tags: provider.tags.join(', '),
+    });
+  };
+
+  const handleSaveMetadata = async () => {
+    try {
+      const { id, ...fields } = editingMetadata;
+      await invoke('update_provider_metadata', {
+        id,
+        metadata: { ...fields, tags: fields.tags.split(',') },
+      });
+      setEditingMetadata(null);
+      await fetchProviders();
+    } catch (e) {
+      setError(t('failMetadata') + errorMessage(e));
+    }
+  };
+
+  const allTags = [...new Set(providers.flatMap((p) => p.tags))].sort();
+
   const sortedProviders = [...providers].sort((a, b) => {
     const aTime = a.last_used_at || 0;
     const bTime = b.last_used_at || 0;
     return bTime - aTime;
   });
 
-  const activeProvider = sortedProviders.length > 0 ? sortedProviders[0] : null;
+  const activeProvider = providers.find((p) => p.id === activeId) || null;
+  const visibleProviders = filteredIds ? sortedProviders.filter((p) => filteredIds.includes(p.id)) : sortedProviders;
 
   return (
     <div className="min-h-screen bg-slate-50 text-slate-900 font-sans p-6 relative">
@@ -194,6 +513,20 @@ export default function App() {
           </div>
           
           <div className="flex items-center gap-3">
+            <button 
+              onClick={handleToggleTargets}
+              className={`p-2.5 rounded-lg transition-colors ${showTargets ? 'text-blue-600 bg-blue-50' : 'text-slate-500 hover:text-slate-700 hover:bg-slate-100'}`}
+              title={t('targetDirs')}
+            >
+              <Settings className="w-5 h-5" />
+            </button>
+            <button
+              onClick={() => confirm(t('confirmSignOut')) && handleSignOut()}
+              className="p-2.5 text-slate-500 hover:text-slate-700 hover:bg-slate-100 rounded-lg transition-colors"
+              title={t('signOut')}
+            >
+              <LogOut className="w-5 h-5" />
+            </button>
             <button 
               onClick={syncActiveAccount}
               disabled={isSyncing}
@@ -203,7 +536,7 @@ export default function App() {
               <RefreshCw className={`w-5 h-5 ${isSyncing ? 'animate-spin' : ''}`} />
             </button>
             <button 
-              onClick={handleStartAuth}
+              onClick={() => handleStartAuth()}
               disabled={isAuthenticating}
               className="flex items-center gap-2 bg-slate-900 hover:bg-slate-800 text-white px-4 py-2.5 rounded-lg font-medium transition-colors disabled:opacity-50"
             >
@@ -220,6 +553,340 @@ export default function App() {
           </div>
         )}
 
+        {switchOutcome && (
+          <div className="bg-white p-4 rounded-2xl shadow-sm border border-slate-100 text-sm space-y-2">
+            <div className="flex justify-between items-center">
+              <span className="font-semibold text-slate-900">{t('switchResult')}</span>
+              <button onClick={() => setSwitchOutcome(null)} className="text-slate-400 hover:text-slate-600">✕</button>
+            </div>
+            {switchOutcome.servers.length > 0 && (
+              <div className="text-xs text-slate-600">
+                {t('hotSwitched')} {switchOutcome.servers.join(', ')}
+              </div>
+            )}
+            {switchOutcome.targets.map((target) => (
+              <div key={target.path} className="space-y-1">
+                <div className="flex items-start gap-2 text-xs">
+                  <span className={`px-2 py-0.5 rounded-full font-medium uppercase tracking-wide text-[10px] ${
+                    target.status === 'written' ? 'bg-green-100 text-green-700'
+                      : target.status === 'failed' ? 'bg-red-100 text-red-700'
+                      : target.status === 'corrupt' ? 'bg-amber-100 text-amber-700'
+                      : 'bg-slate-100 text-slate-500'
+                  }`}>
+                    {t('status_' + target.status)}
+                  </span>
+                  <span className="font-mono text-slate-600 break-all">{target.path}</span>
+                  {target.error && target.status !== 'corrupt' && <span className="text-red-600">{target.error}</span>}
+                </div>
+                {/* A corrupt file was left alone: the user repairs it from the copy or stops using that directory */}
+                {target.status === 'corrupt' && (
+                  <div className="ml-2 pl-3 border-l-2 border-amber-200 text-xs text-slate-600 space-y-1">
+                    <p>{target.error}</p>
+                    <button
+                      onClick={() => handleTargetDirAction('set_target_dir_enabled', {
+                        path: target.path.replace(/[\\/]auth\.json$/, ''),
+                        enabled: false,
+                      })}
+                      className="px-2 py-1 bg-slate-100 hover:bg-slate-200 text-slate-700 rounded transition-colors"
+                    >
+                      {t('stopUsingDirectory')}
+                    </button>
+                  </div>
+                )}
+              </div>
+            ))}
+            {switchOutcome.reload && (
+              <div className="text-xs text-slate-600">
+                {t('restartedProcesses')}: {switchOutcome.reload.processes.length} ({switchOutcome.reload.method})
+              </div>
+            )}
+            {switchOutcome.reload_error && (
+              <div className="text-xs text-red-600">{t('failRestart')}{switchOutcome.reload_error}</div>
+            )}
+            {switchOutcome.copilot_token_error && (
+              <div className="text-xs text-amber-600">{t('failCopilotToken')}{switchOutcome.copilot_token_error}</div>
+            )}
+          </div>
+        )}
+
+        {vaultStatus && vaultStatus.initialized && !vaultStatus.unlocked && (
+          <div className="bg-amber-50 border border-amber-200 p-6 rounded-2xl space-y-3">
+            <div className="flex items-center gap-2 text-amber-800 font-medium">
+              <Lock className="w-5 h-5" />
+              {t('vaultLocked')}
+            </div>
+            <p className="text-sm text-amber-700">{t('vaultLockedDesc')}</p>
+            <div className="flex gap-2">
+              <input
+                type="password"
+                value={passphrase}
+                onChange={(e) => setPassphrase(e.target.value)}
+                onKeyDown={(e) => e.key === 'Enter' && handleUnlockVault()}
+                placeholder={t('passphrase')}
+                className="flex-1 px-3 py-2 text-sm border border-amber-200 rounded-lg outline-none focus:border-amber-400 bg-white"
+              />
+              <button
+                onClick={handleUnlockVault}
+                className="px-4 py-2 bg-slate-900 hover:bg-slate-800 text-white text-sm font-medium rounded-lg transition-colors"
+              >
+                {t('unlock')}
+              </button>
+            </div>
+          </div>
+        )}
+
+        {showTargets && (
+          <div className="bg-white p-6 rounded-2xl shadow-sm border border-slate-100 space-y-4">
+            <div>
+              <h2 className="text-sm font-semibold text-slate-900">{t('targetDirs')}</h2>
+              <p className="text-xs text-slate-500 mt-1">{t('targetDirsDesc')}</p>
+            </div>
+            <div className="space-y-2">
+              {targetDirs.map((dir) => (
+                <div key={dir.path} className="flex items-center gap-3 text-sm">
+                  <input
+                    type="checkbox"
+                    checked={dir.enabled}
+                    onChange={(e) => handleTargetDirAction('set_target_dir_enabled', { path: dir.path, enabled: e.target.checked })}
+                    className="w-4 h-4 accent-blue-600 cursor-pointer"
+                  />
+                  <FolderOpen className="w-4 h-4 text-slate-400 shrink-0" />
+                  <span className={`font-mono text-xs truncate flex-1 ${dir.enabled ? 'text-slate-700' : 'text-slate-400 line-through'}`} title={dir.path}>
+                    {dir.path}
+                  </span>
+                  <span className="bg-slate-100 text-slate-500 text-[10px] px-2 py-0.5 rounded-full font-medium uppercase tracking-wide">
+                    {dir.source}
+                  </span>
+                  {dir.custom && (
+                    <button
+                      onClick={() => handleTargetDirAction('remove_target_dir', { path: dir.path })}
+                      className="p-1 text-slate-400 hover:text-red-500 hover:bg-red-50 rounded transition-colors"
+                      title={t('removeDirectory')}
+                    >
+                      <Trash2 className="w-4 h-4" />
+                    </button>
+                  )}
+                </div>
+              ))}
+            </div>
+            <div className="flex items-center gap-2">
+              <input
+                type="text"
+                value={newTargetDir}
+                onChange={(e) => setNewTargetDir(e.target.value)}
+                onKeyDown={(e) => e.key === 'Enter' && handleAddTargetDir()}
+                placeholder={t('dirPathPlaceholder')}
+                className="flex-1 px-3 py-2 text-sm font-mono border border-slate-200 rounded-lg outline-none focus:border-blue-300"
+              />
+              <button
+                onClick={handleAddTargetDir}
+                className="flex items-center gap-1 px-3 py-2 bg-slate-100 hover:bg-slate-200 text-slate-700 text-sm font-medium rounded-lg transition-colors"
+              >
+                <Plus className="w-4 h-4" />
+                {t('addDirectory')}
+              </button>
+            </div>
+            <div className="pt-4 border-t border-slate-100 space-y-3">
+              <div className="flex items-center justify-between gap-4">
+                <p className="text-xs text-slate-500">{t('normalizeDesc')}</p>
+                <button
+                  onClick={handlePreviewNormalize}
+                  className="shrink-0 px-3 py-2 bg-slate-100 hover:bg-slate-200 text-slate-700 text-sm font-medium rounded-lg transition-colors"
+                >
+                  {t('normalize')}
+                </button>
+              </div>
+              {normalizePreview && (
+                <div className="space-y-3">
+                  {normalizePreview.map((preview) => (
+                    <div key={preview.path}>
+                      <div className="text-xs font-mono text-slate-600 mb-1">{preview.path}</div>
+                      {preview.error ? (
+                        <p className="text-xs text-red-600">{preview.error}</p>
+                      ) : (
+                        <pre className="text-xs bg-slate-50 border border-slate-200 rounded-lg p-3 overflow-x-auto whitespace-pre">{preview.diff}</pre>
+                      )}
+                    </div>
+                  ))}
+                  <div className="flex justify-end gap-2">
+                    <button
+                      onClick={() => setNormalizePreview(null)}
+                      className="px-3 py-2 text-sm text-slate-500 hover:text-slate-700"
+                    >
+                      {t('cancel')}
+                    </button>
+                    <button
+                      onClick={handleApplyNormalize}
+                      className="px-3 py-2 bg-slate-900 hover:bg-slate-800 text-white text-sm font-medium rounded-lg transition-colors"
+                    >
+                      {t('applyChanges')}
+                    </button>
+                  </div>
+                </div>
+              )}
+            </div>
+            {vaultStatus && (!vaultStatus.initialized || vaultStatus.unlocked) && (
+              <div className="pt-4 border-t border-slate-100 space-y-3">
+                <div className="flex items-center justify-between gap-4">
+                  <p className="text-xs text-slate-500">{vaultStatus.initialized ? t('vaultEnabledDesc') : t('vaultDesc')}</p>
+                  {vaultStatus.initialized && (
+                    <button
+                      onClick={() => handleVaultAction('lock_vault')}
+                      className="shrink-0 flex items-center gap-1 px-3 py-2 bg-slate-100 hover:bg-slate-200 text-slate-700 text-sm font-medium rounded-lg transition-colors"
+                    >
+                      <Lock className="w-4 h-4" />
+                      {t('lockVault')}
+                    </button>
+                  )}
+                </div>
+                <div className="flex gap-2">
+                  {vaultStatus.initialized && (
+                    <input
+                      type="password"
+                      value={passphrase}
+                      onChange={(e) => setPassphrase(e.target.value)}
+                      placeholder={t('currentPassphrase')}
+                      className="flex-1 px-3 py-2 text-sm border border-slate-200 rounded-lg outline-none focus:border-blue-400"
+                    />
+                  )}
+                  <input
+                    type="password"
+                    value={newPassphrase}
+                    onChange={(e) => setNewPassphrase(e.target.value)}
+                    placeholder={t('newPassphrase')}
+                    className="flex-1 px-3 py-2 text-sm border border-slate-200 rounded-lg outline-none focus:border-blue-400"
+                  />
+                  <button
+                    onClick={vaultStatus.initialized ? handleChangePassphrase : handleSetupVault}
+                    className="px-3 py-2 bg-slate-100 hover:bg-slate-200 text-slate-700 text-sm font-medium rounded-lg transition-colors"
+                  >
+                    {vaultStatus.initialized ? t('changePassphrase') : t('enableVault')}
+                  </button>
+                </div>
+              </div>
+            )}
+            <div className="pt-4 border-t border-slate-100 space-y-3">
+              <p className="text-xs text-slate-500">{t('transferDesc')}</p>
+              <div className="flex gap-2">
+                <input
+                  value={transferPath}
+                  onChange={(e) => setTransferPath(e.target.value)}
+                  placeholder={t('transferPathPlaceholder')}
+                  className="flex-1 px-3 py-2 text-sm border border-slate-200 rounded-lg outline-none focus:border-blue-400 font-mono"
+                />
+                <input
+                  type="password"
+                  value={transferPassphrase}
+                  onChange={(e) => setTransferPassphrase(e.target.value)}
+                  placeholder={t('passphrase')}
+                  className="w-40 px-3 py-2 text-sm border border-slate-200 rounded-lg outline-none focus:border-blue-400"
+                />
+                <button
+                  onClick={handleExportAccounts}
+                  className="px-3 py-2 bg-slate-100 hover:bg-slate-200 text-slate-700 text-sm font-medium rounded-lg transition-colors"
+                >
+                  {t('exportAccounts')}
+                </button>
+                <button
+                  onClick={() => handleImportAccounts(false)}
+                  className="px-3 py-2 bg-slate-100 hover:bg-slate-200 text-slate-700 text-sm font-medium rounded-lg transition-colors"
+                >
+                  {t('importAccounts')}
+                </button>
+                <button
+                  onClick={() => handleImportAccounts(true)}
+                  className="px-3 py-2 bg-slate-100 hover:bg-slate-200 text-slate-700 text-sm font-medium rounded-lg transition-colors"
+                  title={t('mergeDatabaseDesc')}
+                >
+                  {t('mergeDatabase')}
+                </button>
+              </div>
+            </div>
+            <div className="pt-4 border-t border-slate-100 space-y-3">
+              <div className="flex items-center justify-between gap-4">
+                <p className="text-xs text-slate-500">{t('authFilesDesc')}</p>
+                <button
+                  onClick={handleInspectAuthFiles}
+                  className="shrink-0 px-3 py-2 bg-slate-100 hover:bg-slate-200 text-slate-700 text-sm font-medium rounded-lg transition-colors"
+                >
+                  {t('inspectAuthFiles')}
+                </button>
+              </div>
+              {authFiles && authFiles.length === 0 && (
+                <p className="text-xs text-slate-400">{t('noAuthFiles')}</p>
+              )}
+              {authFiles && authFiles.map((file) => (
+                <div key={file.path} className="flex items-center gap-3 text-sm">
+                  <div className="flex-1 min-w-0">
+                    <div className="font-mono text-xs text-slate-600 truncate" title={file.path}>{file.path}</div>
+                    <div className="text-xs text-slate-400">
+                      {file.error ? file.error : (file.login || t('noCopilotEntry'))}
+                      {file.modified_at && ` · ${new Date(file.modified_at * 1000).toLocaleString()}`}
+                    </div>
+                  </div>
+                  {file.login && (
+                    <span className={`text-xs px-2 py-0.5 rounded ${file.matches_active ? 'bg-green-100 text-green-700' : 'bg-amber-100 text-amber-700'}`}>
+                      {file.matches_active ? t('active') : t('mismatch')}
+                    </span>
+                  )}
+                  {file.provider_id && (
+                    <button
+                      onClick={() => handleAlignAuthFiles(file.provider_id)}
+                      className="shrink-0 px-2 py-1 text-xs text-slate-600 hover:text-slate-900 bg-slate-100 hover:bg-slate-200 rounded transition-colors"
+                    >
+                      {t('useEverywhere')}
+                    </button>
+                  )}
+                </div>
+              ))}
+            </div>
+            <div className="pt-4 border-t border-slate-100 space-y-3">
+              <div className="flex items-center justify-between gap-4">
+                <p className="text-xs text-slate-500">{t('snapshotsDesc')}</p>
+                <button
+                  onClick={handleToggleSnapshots}
+                  className="shrink-0 flex items-center gap-1 px-3 py-2 bg-slate-100 hover:bg-slate-200 text-slate-700 text-sm font-medium rounded-lg transition-colors"
+                >
+                  <RotateCcw className="w-4 h-4" />
+                  {t('snapshots')}
+                </button>
+              </div>
+              {snapshots && snapshots.length === 0 && (
+                <p className="text-xs text-slate-400">{t('noSnapshots')}</p>
+              )}
+              {snapshots && snapshots.map((snapshot) => (
+                <div key={`${snapshot.target_path}-${snapshot.id}`} className="space-y-2">
+                  <div className="flex items-center gap-3 text-sm">
+                    <div className="flex-1 min-w-0">
+                      <div className="font-mono text-xs text-slate-600 truncate" title={snapshot.target_path}>{snapshot.target_path}</div>
+                      <div className="text-xs text-slate-400">
+                        {new Date(snapshot.created_at * 1000).toLocaleString()} · {snapshot.size} B
+                      </div>
+                    </div>
+                    <button
+                      onClick={() => handleDiffSnapshot(snapshot)}
+                      className="shrink-0 px-2 py-1 text-xs text-slate-600 hover:text-slate-900 bg-slate-100 hover:bg-slate-200 rounded transition-colors"
+                    >
+                      {t('showChanges')}
+                    </button>
+                    <button
+                      onClick={() => handleRestoreSnapshot(snapshot)}
+                      className="shrink-0 px-2 py-1 text-xs text-slate-600 hover:text-slate-900 bg-slate-100 hover:bg-slate-200 rounded transition-colors"
+                    >
+                      {t('restore')}
+                    </button>
+                  </div>
+                  {snapshotDiff && snapshotDiff.id === snapshot.id && snapshotDiff.target_path === snapshot.target_path && (
+                    <pre className="text-xs bg-slate-50 border border-slate-200 rounded-lg p-3 overflow-x-auto whitespace-pre">
+                      {snapshotDiff.diff || t('snapshotUnchanged')}
+                    </pre>
+                  )}
+                </div>
+              ))}
+            </div>
+          </div>
+        )}
+
         {isAuthenticating && deviceCode && (
           <div className="bg-blue-50 border border-blue-200 p-6 rounded-2xl flex flex-col items-center text-center space-y-4 relative">
             <div className="w-12 h-12 bg-blue-100 text-blue-600 rounded-full flex items-center justify-center mb-2">
@@ -267,9 +934,59 @@ export default function App() {
         )}
 
         <div className="space-y-4">
-          <h3 className="text-sm font-semibold text-slate-400 uppercase tracking-wider pl-2">
-            {t('yourAccounts')} ({providers.length})
-          </h3>
+          <div className="flex items-center justify-between">
+            <h3 className="text-sm font-semibold text-slate-400 uppercase tracking-wider pl-2">
+              {t('yourAccounts')} ({providers.length})
+            </h3>
+            <div className="flex items-center gap-2">
+              <button
+                onClick={() => handleSwitchBack()}
+                className="p-1.5 text-slate-400 hover:text-slate-700 hover:bg-slate-100 rounded-lg transition-colors"
+                title={t('switchBack')}
+              >
+                <Undo2 className="w-4 h-4" />
+              </button>
+              <button
+                onClick={handleToggleHistory}
+                className={`p-1.5 rounded-lg transition-colors ${history ? 'text-blue-600 bg-blue-50' : 'text-slate-400 hover:text-slate-700 hover:bg-slate-100'}`}
+                title={t('switchHistory')}
+              >
+                <History className="w-4 h-4" />
+              </button>
+              {allTags.length > 0 && (
+                <select
+                  value={tagFilter}
+                  onChange={(e) => setTagFilter(e.target.value)}
+                  className="text-sm text-slate-600 bg-white border border-slate-200 rounded-lg px-2 py-1 outline-none"
+                >
+                  <option value="">{t('allTags')}</option>
+                  {allTags.map((tag) => (
+                    <option key={tag} value={tag}>{tag}</option>
+                  ))}
+                </select>
+              )}
+            </div>
+          </div>
+
+          {history && (
+            <div className="bg-white border border-slate-200 rounded-2xl p-4 space-y-2">
+              {history.length === 0 && (
+                <p className="text-xs text-slate-400">{t('noHistory')}</p>
+              )}
+              {history.map((event) => (
+                <div key={event.id} className="flex items-center gap-2 text-xs text-slate-600">
+                  <span className="text-slate-400 w-36 shrink-0">{new Date(event.created_at * 1000).toLocaleString()}</span>
+                  <span className="truncate">
+                    {event.from_name || '—'} → {event.to_provider_id ? (event.to_name || '?') : t('signedOut')}
+                  </span>
+                  <span className="bg-slate-100 text-slate-500 px-1.5 py-0.5 rounded">{t('trigger_' + event.triggered_by)}</span>
+                  {event.outcome === 'failed' && (
+                    <span className="bg-red-50 text-red-600 px-1.5 py-0.5 rounded" title={event.error || ''}>{t('status_failed')}</span>
+                  )}
+                </div>
+              ))}
+            </div>
+          )}
           
           {loading && providers.length === 0 ? (
             <div className="flex justify-center py-12">
@@ -285,17 +1002,18 @@ export default function App() {
             </div>
           ) : (
             <div className="grid gap-4">
-              {sortedProviders.map((provider) => {
+              {visibleProviders.map((provider) => {
                 const isActive = activeProvider && activeProvider.id === provider.id;
                 
                 return (
+                  <div key={provider.id}>
                   <div 
-                    key={provider.id} 
                     className={`flex items-center justify-between p-5 rounded-2xl border transition-all ${
                       isActive 
                         ? 'bg-white border-blue-200 ring-1 ring-blue-100 shadow-sm' 
                         : 'bg-white border-slate-200 hover:border-slate-300'
                     }`}
+                    style={provider.color ? { borderLeft: `4px solid ${provider.color}` } : undefined}
                   >
                     <div className="flex items-center gap-4">
                       <div className={`w-10 h-10 rounded-full flex items-center justify-center overflow-hidden ${
@@ -310,13 +1028,33 @@ export default function App() {
                       <div>
                         <div className="flex items-center gap-2">
                           <span className="font-semibold text-slate-900">
-                            {provider.name || 'Unknown User'}
+                            {provider.display_name || provider.name || 'Unknown User'}
                           </span>
+                          {provider.display_name && (
+                            <span className="text-xs text-slate-400">@{provider.name}</span>
+                          )}
+                          {provider.tags.map((tag) => (
+                            <span key={tag} className="bg-slate-100 text-slate-600 text-[10px] px-2 py-0.5 rounded-full">
+                              {tag}
+                            </span>
+                          ))}
                           {isActive && (
                             <span className="bg-blue-100 text-blue-700 text-[10px] px-2 py-0.5 rounded-full font-medium uppercase tracking-wide">
                               {t('active')}
                             </span>
                           )}
+                          {provider.token_status && provider.token_status !== 'valid' && (
+                            <span
+                              className={`text-[10px] px-2 py-0.5 rounded-full font-medium uppercase tracking-wide ${
+                                provider.token_status === 'revoked' ? 'bg-red-100 text-red-700' : 'bg-amber-100 text-amber-700'
+                              }`}
+                              title={t('lastChecked')
+                                .replace('{time}', formatDistanceToNow(provider.last_validated_at * 1000, { addSuffix: true, locale: getDateLocale() }))
+                                .replace('{status}', provider.last_http_status ?? '-')}
+                            >
+                              {provider.token_status === 'revoked' ? t('tokenRevoked') : t('tokenCheckFailed')}
+                            </span>
+                          )}
                         </div>
                         <div className="text-xs text-slate-500 mt-1 flex items-center gap-2">
                           <span>{t('added')}: {provider.created_at ? new Date(provider.created_at * 1000).toLocaleDateString() : 'Unknown'}</span>
@@ -326,7 +1064,16 @@ export default function App() {
                               <span>{t('lastUsed')}: {formatDistanceToNow(provider.last_used_at * 1000, { addSuffix: true, locale: getDateLocale() })}</span>
                             </>
                           )}
+                          {provider.token_last4 && (
+                            <>
+                              <span className="w-1 h-1 bg-slate-300 rounded-full"></span>
+                              <span className="font-mono" title={`SHA-256 ${provider.token_hash}`}>{t('token')}: …{provider.token_last4}</span>
+                            </>
+                          )}
                         </div>
+                        {provider.notes && (
+                          <p className="text-xs text-slate-500 mt-1 whitespace-pre-line">{provider.notes}</p>
+                        )}
                       </div>
                     </div>
                     
@@ -340,6 +1087,27 @@ export default function App() {
                         </button>
                       )}
                       
+                      <button
+                        onClick={() => handleStartAuth(provider.id)}
+                        disabled={isAuthenticating}
+                        className={`p-2 rounded-lg transition-colors disabled:opacity-50 ${
+                          provider.token_status === 'revoked'
+                            ? 'text-red-500 hover:bg-red-50'
+                            : 'text-slate-400 hover:text-slate-700 hover:bg-slate-100'
+                        }`}
+                        title={t('reauthorize')}
+                      >
+                        <KeyRound className="w-5 h-5" />
+                      </button>
+
+                      <button
+                        onClick={() => handleEditMetadata(provider)}
+                        className="p-2 text-slate-400 hover:text-slate-700 hover:bg-slate-100 rounded-lg transition-colors"
+                        title={t('editLabels')}
+                      >
+                        <Pencil className="w-5 h-5" />
+                      </button>
+
                       <button
                         onClick={() => handleDelete(provider.id)}
                         className="p-2 text-slate-400 hover:text-red-500 hover:bg-red-50 rounded-lg transition-colors"
@@ -349,6 +1117,59 @@ export default function App() {
                       </button>
                     </div>
                   </div>
+                  {editingMetadata && editingMetadata.id === provider.id && (
+                    <div className="mt-2 p-4 bg-white border border-slate-200 rounded-2xl space-y-2">
+                      <div className="flex gap-2">
+                        <input
+                          value={editingMetadata.display_name}
+                          onChange={(e) => setEditingMetadata({ ...editingMetadata, display_name: e.target.value })}
+                          placeholder={t('displayName')}
+                          className="flex-1 px-3 py-2 text-sm border border-slate-200 rounded-lg outline-none focus:border-blue-400"
+                        />
+                        <input
+                          type="color"
+                          value={editingMetadata.color || '#94a3b8'}
+                          onChange={(e) => setEditingMetadata({ ...editingMetadata, color: e.target.value })}
+                          className="w-10 h-10 border border-slate-200 rounded-lg cursor-pointer"
+                          title={t('color')}
+                        />
+                      </div>
+                      <input
+                        value={editingMetadata.tags}
+                        onChange={(e) => setEditingMetadata({ ...editingMetadata, tags: e.target.value })}
+                        placeholder={t('tagsPlaceholder')}
+                        className="w-full px-3 py-2 text-sm border border-slate-200 rounded-lg outline-none focus:border-blue-400"
+                      />
+                      <textarea
+                        value={editingMetadata.notes}
+                        onChange={(e) => setEditingMetadata({ ...editingMetadata, notes: e.target.value })}
+                        placeholder={t('notes')}
+                        rows={2}
+                        className="w-full px-3 py-2 text-sm border border-slate-200 rounded-lg outline-none focus:border-blue-400"
+                      />
+                      <div className="flex justify-end gap-2">
+                        <button
+                          onClick={() => setEditingMetadata({ ...editingMetadata, color: '' })}
+                          className="px-3 py-2 text-sm text-slate-500 hover:text-slate-700 mr-auto"
+                        >
+                          {t('clearColor')}
+                        </button>
+                        <button
+                          onClick={() => setEditingMetadata(null)}
+                          className="px-3 py-2 text-sm text-slate-500 hover:text-slate-700"
+                        >
+                          {t('cancel')}
+                        </button>
+                        <button
+                          onClick={handleSaveMetadata}
+                          className="px-3 py-2 bg-slate-900 hover:bg-slate-800 text-white text-sm font-medium rounded-lg transition-colors"
+                        >
+                          {t('save')}
+                        </button>
+                      </div>
+                    </div>
+                  )}
+                  </div>
                 );
               })}
             </div>
@@ -22,6 +22,97 @@ export const translations = {
     copyCode: "Copy code",
     codeCopiedToast: "Verification code copied to clipboard! Please paste it in the browser.",
     syncAccount: "Sync current OpenCode account",
+    targetDirs: "OpenCode directories",
+    targetDirsDesc: "auth.json is written to every enabled directory. Add custom locations for relocated or containerised OpenCode data.",
+    addDirectory: "Add",
+    removeDirectory: "Remove directory",
+    dirPathPlaceholder: "/absolute/path/to/opencode",
+    failTargetDir: "Failed to update directories: ",
+    normalize: "Review cleanup",
+    normalizeDesc: "Remove duplicate GitHub Copilot entries written in the layout your auth.json does not use.",
+    nothingToNormalize: "Every auth.json already uses a single layout.",
+    normalizeDone: "auth.json files cleaned up.",
+    applyChanges: "Apply changes",
+    failNormalize: "Failed to clean up auth.json: ",
+    switchResult: "Switch result",
+    hotSwitched: "Hot-switched through OpenCode server:",
+    status_written: "Written",
+    status_skipped: "Skipped",
+    status_failed: "Failed",
+    status_corrupt: "Corrupt",
+    stopUsingDirectory: "Stop writing to this directory",
+    restartedProcesses: "Restarted OpenCode processes",
+    failRestart: "Failed to restart OpenCode: ",
+    authFilesDesc: "Check which GitHub account each auth.json is signed in with.",
+    inspectAuthFiles: "Check accounts",
+    noAuthFiles: "No auth.json files found.",
+    noCopilotEntry: "No GitHub Copilot entry",
+    mismatch: "Differs",
+    useEverywhere: "Use everywhere",
+    failInspect: "Failed to check auth.json files: ",
+    signOut: "Sign out of GitHub Copilot in OpenCode",
+    confirmSignOut: "Remove the GitHub Copilot credential from every auth.json and restart OpenCode?",
+    confirmSignOutAfterDelete: "This was the active account. Also sign OpenCode out of GitHub Copilot?",
+    failSignOut: "Failed to sign out: ",
+    vaultLocked: "Tokens are locked",
+    vaultLockedDesc: "Enter your master passphrase to switch accounts or add new ones.",
+    passphrase: "Master passphrase",
+    unlock: "Unlock",
+    vaultDesc: "Encrypt stored GitHub tokens with a master passphrase. You will need it each time the app starts. Database backups made during app upgrades hold the tokens unencrypted and are deleted when you turn this on.",
+    vaultEnabledDesc: "Stored GitHub tokens are encrypted with your master passphrase.",
+    enableVault: "Encrypt tokens",
+    lockVault: "Lock",
+    currentPassphrase: "Current passphrase",
+    newPassphrase: "New passphrase",
+    changePassphrase: "Change passphrase",
+    vaultEnabled: "Tokens are now encrypted.",
+    passphraseChanged: "Passphrase changed.",
+    failVault: "Token vault error: ",
+    token: "Token",
+    allTags: "All tags",
+    editLabels: "Edit labels",
+    displayName: "Display name",
+    color: "Color",
+    clearColor: "No color",
+    tagsPlaceholder: "Tags, comma separated (e.g. work, personal, oss)",
+    notes: "Notes",
+    save: "Save",
+    failMetadata: "Failed to save labels: ",
+    switchBack: "Switch back to previous account",
+    switchHistory: "Switch history",
+    noHistory: "No switches yet.",
+    signedOut: "Signed out",
+    trigger_ui: "App",
+    trigger_cli: "CLI",
+    trigger_rule: "Rule",
+    trigger_sync: "External",
+    failHistory: "Failed to load history: ",
+    transferDesc: "Move your accounts to another machine with a passphrase-encrypted file that includes their tokens.",
+    transferPathPlaceholder: "/absolute/path/to/accounts.json",
+    exportAccounts: "Export",
+    importAccounts: "Import",
+    mergeDatabase: "Merge providers.db",
+    mergeDatabaseDesc: "Merge the accounts from another install's providers.db. Enter its passphrase only if its tokens were encrypted.",
+    exportDone: "Exported {count} accounts.",
+    importDone: "Import finished: {added} added, {updated} updated, {skipped} skipped.",
+    failTransfer: "Failed to transfer accounts: ",
+    tokenRevoked: "Revoked",
+    tokenCheckFailed: "Check failed",
+    lastChecked: "Last checked {time}, HTTP {status}",
+    tokenRevokedNotice: "GitHub no longer accepts the token for {name}. Re-authorize the account to get a new one.",
+    confirmForceSwitch: "Switch anyway without verifying the token?",
+    reauthorize: "Re-authorize",
+    reauthorized: "Account re-authorized",
+    snapshots: "Backups",
+    snapshotsDesc: "Every auth.json is backed up before the app changes it. Restore a backup to undo a change.",
+    noSnapshots: "No backups yet.",
+    showChanges: "Changes",
+    restore: "Restore",
+    snapshotUnchanged: "Identical to the current file.",
+    confirmRestoreSnapshot: "Replace {path} with this backup? OpenCode will be restarted.",
+    snapshotRestored: "Backup restored",
+    failSnapshots: "Failed to access backups: ",
+    failCopilotToken: "Could not fetch a Copilot token, OpenCode will request one itself: ",
   },
   zh: {
     appTitle: "OpenCode Switcher",
@@ -46,6 +137,97 @@ export const translations = {
     copyCode: "复制验证码",
     codeCopiedToast: "验证码已自动复制到剪贴板！请在弹出的浏览器中粘贴。",
     syncAccount: "同步当前 OpenCode 账号",
+    targetDirs: "OpenCode 目录",
+    targetDirsDesc: "auth.json 会写入所有已启用的目录。可为迁移过或容器化的 OpenCode 数据添加自定义位置。",
+    addDirectory: "添加",
+    removeDirectory: "移除目录",
+    dirPathPlaceholder: "/absolute/path/to/opencode",
+    failTargetDir: "更新目录失败: ",
+    normalize: "检查清理",
+    normalizeDesc: "移除以 auth.json 未使用的格式写入的重复 GitHub Copilot 条目。",
+    nothingToNormalize: "所有 auth.json 均只使用一种格式。",
+    normalizeDone: "auth.json 已清理完成。",
+    applyChanges: "应用更改",
+    failNormalize: "清理 auth.json 失败: ",
+    switchResult: "切换结果",
+    hotSwitched: "已通过 OpenCode 服务热切换：",
+    status_written: "已写入",
+    status_skipped: "已跳过",
+    status_failed: "失败",
+    status_corrupt: "已损坏",
+    stopUsingDirectory: "不再写入此目录",
+    restartedProcesses: "已重启的 OpenCode 进程",
+    failRestart: "重启 OpenCode 失败: ",
+    authFilesDesc: "检查每个 auth.json 当前登录的 GitHub 账号。",
+    inspectAuthFiles: "检查账号",
+    noAuthFiles: "未找到 auth.json 文件。",
+    noCopilotEntry: "没有 GitHub Copilot 凭证",
+    mismatch: "不一致",
+    useEverywhere: "全部使用此账号",
+    failInspect: "检查 auth.json 失败: ",
+    signOut: "在 OpenCode 中退出 GitHub Copilot",
+    confirmSignOut: "从所有 auth.json 中移除 GitHub Copilot 凭证并重启 OpenCode？",
+    confirmSignOutAfterDelete: "这是当前使用的账号。是否同时让 OpenCode 退出 GitHub Copilot？",
+    failSignOut: "退出失败: ",
+    vaultLocked: "令牌已锁定",
+    vaultLockedDesc: "请输入主密码以切换或添加账号。",
+    passphrase: "主密码",
+    unlock: "解锁",
+    vaultDesc: "使用主密码加密保存的 GitHub 令牌。每次启动应用时都需要输入该密码。应用升级时生成的数据库备份中令牌未加密，开启后这些备份将被删除。",
+    vaultEnabledDesc: "保存的 GitHub 令牌已使用主密码加密。",
+    enableVault: "加密令牌",
+    lockVault: "锁定",
+    currentPassphrase: "当前密码",
+    newPassphrase: "新密码",
+    changePassphrase: "修改密码",
+    vaultEnabled: "令牌已加密。",
+    passphraseChanged: "密码已修改。",
+    failVault: "令牌保险库错误: ",
+    token: "令牌",
+    allTags: "全部标签",
+    editLabels: "编辑标签",
+    displayName: "显示名称",
+    color: "颜色",
+    clearColor: "无颜色",
+    tagsPlaceholder: "标签，用逗号分隔（如 work, personal, oss）",
+    notes: "备注",
+    save: "保存",
+    failMetadata: "保存标签失败: ",
+    switchBack: "切换回上一个账号",
+    switchHistory: "切换记录",
+    noHistory: "暂无切换记录。",
+    signedOut: "已退出",
+    trigger_ui: "应用",
+    trigger_cli: "命令行",
+    trigger_rule: "规则",
+    trigger_sync: "外部",
+    failHistory: "加载记录失败: ",
+    transferDesc: "使用密码加密的文件（包含令牌）将账号迁移到另一台电脑。",
+    transferPathPlaceholder: "/absolute/path/to/accounts.json",
+    exportAccounts: "导出",
+    importAccounts: "导入",
+    mergeDatabase: "合并 providers.db",
+    mergeDatabaseDesc: "合并另一份安装中 providers.db 的账号。仅当其令牌已加密时才需要输入其密码。",
+    exportDone: "已导出 {count} 个账号。",
+    importDone: "导入完成：新增 {added} 个，更新 {updated} 个，跳过 {skipped} 个。",
+    failTransfer: "迁移账号失败: ",
+    tokenRevoked: "已失效",
+    tokenCheckFailed: "检查失败",
+    lastChecked: "上次检查 {time}，HTTP {status}",
+    tokenRevokedNotice: "GitHub 已不再接受 {name} 的令牌。请重新授权该账号以获取新令牌。",
+    confirmForceSwitch: "不验证令牌，仍然切换？",
+    reauthorize: "重新授权",
+    reauthorized: "账号已重新授权",
+    snapshots: "备份",
+    snapshotsDesc: "应用修改 auth.json 之前都会先备份。恢复备份即可撤销修改。",
+    noSnapshots: "暂无备份。",
+    showChanges: "变更",
+    restore: "恢复",
+    snapshotUnchanged: "与当前文件相同。",
+    confirmRestoreSnapshot: "用此备份替换 {path}？OpenCode 将会重启。",
+    snapshotRestored: "备份已恢复",
+    failSnapshots: "访问备份失败: ",
+    failCopilotToken: "无法获取 Copilot 令牌，OpenCode 将自行获取: ",
   },
   ja: {
     appTitle: "OpenCode Switcher",
@@ -70,6 +252,97 @@ export const translations = {
     copyCode: "コードをコピー",
     codeCopiedToast: "認証コードがクリップボードにコピーされました！ブラウザで貼り付けてください。",
     syncAccount: "現在ログイン中のアカウントを同期",
+    targetDirs: "OpenCode ディレクトリ",
+    targetDirsDesc: "auth.json は有効なすべてのディレクトリに書き込まれます。移動したりコンテナ化した OpenCode データ用にカスタムの場所を追加できます。",
+    addDirectory: "追加",
+    removeDirectory: "ディレクトリを削除",
+    dirPathPlaceholder: "/absolute/path/to/opencode",
+    failTargetDir: "ディレクトリの更新に失敗しました: ",
+    normalize: "クリーンアップを確認",
+    normalizeDesc: "auth.json が使用していない形式で書き込まれた重複した GitHub Copilot エントリを削除します。",
+    nothingToNormalize: "すべての auth.json は単一の形式を使用しています。",
+    normalizeDone: "auth.json をクリーンアップしました。",
+    applyChanges: "変更を適用",
+    failNormalize: "auth.json のクリーンアップに失敗しました: ",
+    switchResult: "切り替え結果",
+    hotSwitched: "OpenCode サーバー経由でホットスイッチしました：",
+    status_written: "書き込み済み",
+    status_skipped: "スキップ",
+    status_failed: "失敗",
+    status_corrupt: "破損",
+    stopUsingDirectory: "このディレクトリへの書き込みを停止",
+    restartedProcesses: "再起動した OpenCode プロセス",
+    failRestart: "OpenCode の再起動に失敗しました: ",
+    authFilesDesc: "各 auth.json がどの GitHub アカウントでログインしているか確認します。",
+    inspectAuthFiles: "アカウントを確認",
+    noAuthFiles: "auth.json が見つかりません。",
+    noCopilotEntry: "GitHub Copilot のエントリがありません",
+    mismatch: "不一致",
+    useEverywhere: "すべてに適用",
+    failInspect: "auth.json の確認に失敗しました: ",
+    signOut: "OpenCode の GitHub Copilot からサインアウト",
+    confirmSignOut: "すべての auth.json から GitHub Copilot の認証情報を削除し、OpenCode を再起動しますか？",
+    confirmSignOutAfterDelete: "これは使用中のアカウントでした。OpenCode を GitHub Copilot からサインアウトしますか？",
+    failSignOut: "サインアウトに失敗しました: ",
+    vaultLocked: "トークンはロックされています",
+    vaultLockedDesc: "アカウントの切り替えや追加にはマスターパスフレーズを入力してください。",
+    passphrase: "マスターパスフレーズ",
+    unlock: "ロック解除",
+    vaultDesc: "保存された GitHub トークンをマスターパスフレーズで暗号化します。アプリを起動するたびに入力が必要です。アプリの更新時に作成されたデータベースのバックアップにはトークンが暗号化されずに含まれるため、有効にすると削除されます。",
+    vaultEnabledDesc: "保存された GitHub トークンはマスターパスフレーズで暗号化されています。",
+    enableVault: "トークンを暗号化",
+    lockVault: "ロック",
+    currentPassphrase: "現在のパスフレーズ",
+    newPassphrase: "新しいパスフレーズ",
+    changePassphrase: "パスフレーズを変更",
+    vaultEnabled: "トークンを暗号化しました。",
+    passphraseChanged: "パスフレーズを変更しました。",
+    failVault: "トークン保管庫のエラー: ",
+    token: "トークン",
+    allTags: "すべてのタグ",
+    editLabels: "ラベルを編集",
+    displayName: "表示名",
+    color: "色",
+    clearColor: "色なし",
+    tagsPlaceholder: "タグ（カンマ区切り、例: work, personal, oss）",
+    notes: "メモ",
+    save: "保存",
+    failMetadata: "ラベルの保存に失敗しました: ",
+    switchBack: "前のアカウントに戻す",
+    switchHistory: "切り替え履歴",
+    noHistory: "切り替え履歴はありません。",
+    signedOut: "サインアウト",
+    trigger_ui: "アプリ",
+    trigger_cli: "CLI",
+    trigger_rule: "ルール",
+    trigger_sync: "外部",
+    failHistory: "履歴の読み込みに失敗しました: ",
+    transferDesc: "トークンを含むパスフレーズで暗号化されたファイルで、アカウントを別のマシンに移行します。",
+    transferPathPlaceholder: "/absolute/path/to/accounts.json",
+    exportAccounts: "エクスポート",
+    importAccounts: "インポート",
+    mergeDatabase: "providers.db を統合",
+    mergeDatabaseDesc: "別のインストールの providers.db からアカウントを統合します。トークンが暗号化されている場合のみパスフレーズを入力してください。",
+    exportDone: "{count} 件のアカウントをエクスポートしました。",
+    importDone: "インポート完了：追加 {added} 件、更新 {updated} 件、スキップ {skipped} 件。",
+    failTransfer: "アカウントの移行に失敗しました: ",
+    tokenRevoked: "失効",
+    tokenCheckFailed: "確認失敗",
+    lastChecked: "最終確認 {time}、HTTP {status}",
+    tokenRevokedNotice: "GitHub は {name} のトークンを受け付けなくなりました。アカウントを再認証して新しいトークンを取得してください。",
+    confirmForceSwitch: "トークンを確認せずに切り替えますか？",
+    reauthorize: "再認証",
+    reauthorized: "アカウントを再認証しました",
+    snapshots: "バックアップ",
+    snapshotsDesc: "アプリが auth.json を変更する前に必ずバックアップします。バックアップを復元すると変更を元に戻せます。",
+    noSnapshots: "バックアップはまだありません。",
+    showChanges: "変更点",
+    restore: "復元",
+    snapshotUnchanged: "現在のファイルと同じです。",
+    confirmRestoreSnapshot: "{path} をこのバックアップで置き換えますか？OpenCode は再起動されます。",
+    snapshotRestored: "バックアップを復元しました",
+    failSnapshots: "バックアップへのアクセスに失敗しました: ",
+    failCopilotToken: "Copilot トークンを取得できませんでした。OpenCode が自分で取得します: ",
   }
 };
 